@@ -0,0 +1,91 @@
+//! Counts the allocations of extracting the links of a large page,
+//! once with the `Vec` based functions and once with the iterator based ones,
+//! and of the per-file pipeline: `extract_links` of the page and `build_graph_from_pages` of
+//! a site of such pages.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use page_graph::{
+    build_graph_from_pages, extract_links, filter_prefix, filter_regex, filter_regex_iter,
+    get_urls_from, urls, Config,
+};
+
+use regex::Regex;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Pages of the site to build a graph of.
+const PAGES: usize = 100;
+
+/// Run `f` and return how many allocations it made, not counting dropping its result.
+fn count<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let links = f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    drop(links);
+    allocations
+}
+
+fn main() {
+    let filter = Regex::new(r"^https?://www.traplinked.com/").unwrap();
+    let prefix = Regex::new(r"^https?://www.traplinked.com/(en/|nl/)?").unwrap();
+
+    let mut html = String::new();
+    for i in 0..10_000 {
+        html.push_str(&format!(
+            r#"<p><a href="https://www.traplinked.com/page/{}">x</a> <a href="https://example.com/{}">y</a></p>"#,
+            i, i
+        ));
+    }
+    // Compile the regex matchers before counting.
+    get_urls_from(&html);
+
+    let vecs = count(|| {
+        filter_regex(&get_urls_from(&html), &filter)
+            .iter()
+            .map(|url| filter_prefix(url, &prefix))
+            .collect::<Vec<_>>()
+    });
+    let iters = count(|| {
+        filter_regex_iter(urls(&html), &filter)
+            .map(|url| filter_prefix(url, &prefix))
+            .collect::<Vec<_>>()
+    });
+
+    println!("allocations with vecs:      {}", vecs);
+    println!("allocations with iterators: {}", iters);
+
+    let config = Config::default();
+    let per_file = count(|| extract_links("page", &html, &config));
+    let pages: Vec<_> = (0..PAGES)
+        .map(|i| (format!("page/{}", i), html.clone()))
+        .collect();
+    let build = count(|| build_graph_from_pages(pages, &config));
+
+    println!("allocations of extract_links: {}", per_file);
+    println!(
+        "allocations of build_graph_from_pages: {} ({} pages, {} per page)",
+        build,
+        PAGES,
+        build / PAGES
+    );
+}
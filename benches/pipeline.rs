@@ -0,0 +1,90 @@
+//! Times link extraction and graph building on generated pages, as a baseline for
+//! optimizations.
+//!
+//! Run with `cargo bench --bench pipeline`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use page_graph::{build_graph_from_pages, extract_links, get_urls_from, make_page_graph, Config};
+
+use std::collections::HashMap;
+use std::fs;
+use std::hint::black_box;
+
+/// Links on the large page, about as many as the longest pages of a real site have.
+const LINKS_PER_PAGE: usize = 10_000;
+
+/// Pages and links of each of them for building graphs, about the size of a mid-sized site.
+const PAGES: usize = 2_000;
+const LINKS_PER_SYNTHETIC_PAGE: usize = 40;
+
+/// A page with `links` links, half of them on the site and half elsewhere, among some text.
+fn html(links: usize) -> String {
+    let mut html = String::from("<html><head><title>Generated</title></head><body>\n");
+    for i in 0..links {
+        html.push_str(&format!(
+            r#"<p>Text <a href="https://www.traplinked.com/page/{}">on the site</a> and <a href="https://example.com/{}" rel="nofollow">elsewhere</a>.</p>
+"#,
+            i / 2,
+            i / 2
+        ));
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// A site of `pages` pages, each linking to `links` others in a deterministic spread.
+fn site(pages: usize, links: usize) -> HashMap<String, Vec<String>> {
+    (0..pages)
+        .map(|page| {
+            let targets = (1..=links)
+                .map(|i| format!("page/{}", (page * 31 + i * 17) % pages))
+                .collect();
+            (format!("page/{}", page), targets)
+        })
+        .collect()
+}
+
+fn extraction(c: &mut Criterion) {
+    let html = html(LINKS_PER_PAGE);
+    let mut group = c.benchmark_group("extraction");
+    group.throughput(Throughput::Bytes(html.len() as u64));
+    group.bench_function("get_urls_from", |b| {
+        b.iter(|| get_urls_from(black_box(&html)))
+    });
+
+    // A page read from disk, its links extracted and normalized.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("page.html");
+    fs::write(&path, &html).unwrap();
+    let config = Config::default();
+    group.bench_function("per_file", |b| {
+        b.iter(|| {
+            let content = fs::read_to_string(&path).unwrap();
+            extract_links("page.html", black_box(&content), &config)
+        })
+    });
+    group.finish();
+}
+
+fn graph_build(c: &mut Criterion) {
+    let config = Config::default();
+    let pages: Vec<_> = (0..PAGES)
+        .map(|i| (format!("page/{}", i), html(LINKS_PER_SYNTHETIC_PAGE)))
+        .collect();
+    let links = site(PAGES, LINKS_PER_SYNTHETIC_PAGE);
+
+    let mut group = c.benchmark_group("graph");
+    group.throughput(Throughput::Elements(PAGES as u64));
+    group.sample_size(20);
+    group.bench_function("build_graph_from_pages", |b| {
+        b.iter(|| build_graph_from_pages(black_box(pages.clone()), &config))
+    });
+    group.bench_function("make_page_graph", |b| {
+        b.iter(|| make_page_graph(black_box(&links)).edge_count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, extraction, graph_build);
+criterion_main!(benches);
@@ -0,0 +1,549 @@
+use petgraph::algo::tarjan_scc;
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use regex::Regex;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{depths, PageGraph, UNFOLLOWED_RELS};
+
+/// Find pairs of pages linking to each other, as two pages redirecting to each other would,
+/// so these are often redirect loops in the making. Sorted, each pair in order.
+pub fn find_mutual_links<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+) -> Vec<(&'a str, &'a str)> {
+    let mut pairs: Vec<_> = graph
+        .all_edges()
+        .filter(|&(page, other, &relation)| relation == "links" && page < other)
+        .filter(|&(page, other, _)| graph.contains_edge(other, page))
+        .map(|(page, other, _)| (page, other))
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Find links between pages which should link to each other but only link one way, like a
+/// product and its category. `pairs` is matched against `page -> other` for each link and
+/// against its reverse, so it needs to match one direction only. Sorted, each link as
+/// `(page, other)` where `other` doesn't link back. Links to themselves and fragments are left
+/// out.
+pub fn find_missing_back_links<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    pairs: &Regex,
+) -> Vec<(&'a str, &'a str)> {
+    let pair = |from: &str, to: &str| pairs.is_match(&format!("{} -> {}", from, to));
+    let mut links: Vec<_> = graph
+        .all_edges()
+        .filter(|&(page, other, &relation)| relation == "links" && page != other)
+        .filter(|&(page, other, _)| !graph.contains_edge(other, page))
+        .filter(|&(page, other, _)| pair(page, other) || pair(other, page))
+        .map(|(page, other, _)| (page, other))
+        .collect();
+    links.sort_unstable();
+    links
+}
+
+/// Find groups of more than two pages linking to each other in cycles, the strongly connected
+/// components of the graph, from each of which links lead to all others. Two pages like that
+/// are `find_mutual_links`. Each group is sorted by name, the groups by their first page.
+pub fn find_closed_cycles<'a>(graph: &GraphMap<&'a str, &'a str, Directed>) -> Vec<Vec<&'a str>> {
+    let mut cycles: Vec<_> = tarjan_scc(graph)
+        .into_iter()
+        .filter(|pages| pages.len() > 2)
+        .map(|mut pages| {
+            pages.sort_unstable();
+            pages
+        })
+        .collect();
+    cycles.sort_unstable();
+    cycles
+}
+
+/// Find pages other than `root` whose only outgoing link, ignoring links to themselves, is to `root`.
+/// Sorted by name.
+pub fn find_leaf_pages<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &str,
+) -> Vec<&'a str> {
+    let mut pages: Vec<_> = graph
+        .nodes()
+        .filter(|&page| page != root)
+        .filter(|&page| {
+            let mut targets = graph.neighbors(page).filter(|&target| target != page);
+            targets.next() == Some(root) && targets.next().is_none()
+        })
+        .collect();
+    pages.sort_unstable();
+    pages
+}
+
+/// Find pages which are only linked from boilerplate like navigation or footers, sorted by name.
+///
+/// A target counts as boilerplate if at least `threshold` (a fraction between 0 and 1) of the
+/// crawled pages link to it. The fewest times any of those pages links to it is its boilerplate
+/// count; a page linking to it more often than that also has an editorial link to it.
+/// Content orphans are the linked pages other than `root` without any editorial inbound link.
+pub fn find_content_orphans<'a>(
+    links: &'a HashMap<String, Vec<String>>,
+    root: &str,
+    threshold: f64,
+) -> Vec<&'a str> {
+    // Target -> how often each page links to it.
+    let mut occurrences: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+    for (source, targets) in links {
+        for target in targets {
+            *occurrences
+                .entry(target.as_str())
+                .or_default()
+                .entry(source.as_str())
+                .or_default() += 1;
+        }
+    }
+
+    let required = threshold * links.len() as f64;
+    let mut orphans: Vec<_> = occurrences
+        .into_iter()
+        .filter(|(target, _)| *target != root)
+        .filter(|(_, sources)| sources.len() as f64 >= required)
+        .filter(|(_, sources)| {
+            let boilerplate = sources.values().min().copied().unwrap_or_default();
+            sources.values().all(|&count| count <= boilerplate)
+        })
+        .map(|(target, _)| target)
+        .collect();
+    orphans.sort_unstable();
+    orphans
+}
+
+/// Find pages reachable from `root` with fewer than `min` inbound links, other than `root` itself.
+/// Links of a page to itself don't count. Sorted by inbound links, then name.
+pub fn find_under_linked<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &'a str,
+    min: usize,
+) -> Vec<(&'a str, usize)> {
+    let mut pages: Vec<_> = depths(graph, root)
+        .into_keys()
+        .filter(|&page| page != root)
+        .map(|page| {
+            let inlinks = graph
+                .neighbors_directed(page, Direction::Incoming)
+                .filter(|&source| source != page)
+                .count();
+            (page, inlinks)
+        })
+        .filter(|&(_, inlinks)| inlinks < min)
+        .collect();
+    pages.sort_unstable_by_key(|&(page, inlinks)| (inlinks, page));
+    pages
+}
+
+/// Find the weakly connected components of `graph`, i.e. the groups of pages connected by links
+/// in either direction. Each is sorted, the largest come first, ties in order of their pages.
+pub fn find_components<'a>(graph: &GraphMap<&'a str, &'a str, Directed>) -> Vec<Vec<&'a str>> {
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+
+    // Flood fill along links in both directions, from each page not seen yet.
+    let mut seen = HashSet::new();
+    let mut components: Vec<Vec<&str>> = Vec::new();
+    for start in nodes {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut pages = vec![start];
+        let mut i = 0;
+        while i < pages.len() {
+            for neighbor in graph
+                .neighbors_directed(pages[i], Direction::Outgoing)
+                .chain(graph.neighbors_directed(pages[i], Direction::Incoming))
+            {
+                if seen.insert(neighbor) {
+                    pages.push(neighbor);
+                }
+            }
+            i += 1;
+        }
+        pages.sort_unstable();
+        components.push(pages);
+    }
+    components.sort_by_key(|pages| std::cmp::Reverse(pages.len()));
+    components
+}
+
+/// Find pages linking to more than `max` other pages, counting each linked page once.
+/// Sorted by outbound links, most first, then by name.
+pub fn find_link_heavy<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    max: usize,
+) -> Vec<(&'a str, usize)> {
+    let mut pages: Vec<_> = graph
+        .nodes()
+        .map(|page| {
+            let outlinks = graph
+                .neighbors_directed(page, Direction::Outgoing)
+                .filter(|&target| target != page)
+                .count();
+            (page, outlinks)
+        })
+        .filter(|&(_, outlinks)| outlinks > max)
+        .collect();
+    pages.sort_unstable_by_key(|&(page, outlinks)| (std::cmp::Reverse(outlinks), page));
+    pages
+}
+
+/// Sum the inbound links of pages sharing their first `depth` path segments,
+/// e.g. `blog` for `blog/post` at depth 1. Sorted by inbound links, most first, then by prefix.
+pub fn inbound_by_prefix(
+    graph: &GraphMap<&str, &str, Directed>,
+    depth: usize,
+) -> Vec<(String, usize)> {
+    let mut prefixes: HashMap<String, usize> = HashMap::new();
+    for (_, target, &kind) in graph.all_edges() {
+        if kind != "links" {
+            continue;
+        }
+        let prefix: Vec<_> = target.split('/').take(depth.max(1)).collect();
+        *prefixes.entry(prefix.join("/")).or_default() += 1;
+    }
+
+    let mut prefixes: Vec<_> = prefixes.into_iter().collect();
+    prefixes.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    prefixes
+}
+
+/// Count the distinct page -> page links which are followed and those which are unfollowed by
+/// each of the `UNFOLLOWED_RELS`, in that order. A link with `rel="nofollow ugc"` counts twice.
+pub fn count_by_rel(page_graph: &PageGraph) -> Vec<(&'static str, usize)> {
+    let followed = page_graph
+        .graph()
+        .all_edges()
+        .filter(|&(page, target, &kind)| kind == "links" && page_graph.is_followed(page, target))
+        .count();
+
+    let mut counts = vec![("followed", followed)];
+    for &rel in UNFOLLOWED_RELS {
+        let count = page_graph
+            .unfollowed()
+            .values()
+            .filter(|rels| rels.contains(rel))
+            .count();
+        counts.push((rel, count));
+    }
+    counts
+}
+
+/// Remove the `orphans` matching any of `patterns`, pages left unlinked on purpose like
+/// `thank-you`. Returns how many were removed.
+pub fn remove_allowed_orphans(orphans: &mut HashSet<&str>, patterns: &[Regex]) -> usize {
+    let before = orphans.len();
+    orphans.retain(|page| !patterns.iter().any(|pattern| pattern.is_match(page)));
+    before - orphans.len()
+}
+
+/// Find orphans to link to so that all `orphans` become reachable through the links between them,
+/// with how many orphans each one reconnects, itself included.
+///
+/// These are the orphans no other orphan links to, each reconnecting all orphans it reaches.
+/// Orphans in cycles not reached from any of them need one more link per cycle; for those the
+/// orphan reaching the most of the rest is picked first, counting only orphans nothing picked
+/// before reconnects. Sorted by orphans reconnected, most first, then by name.
+pub fn find_orphan_entries<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    orphans: &HashSet<&'a str>,
+) -> Vec<(&'a str, usize)> {
+    // The orphans reachable from `start` through other orphans not yet `covered`.
+    let reach = |start: &'a str, covered: &HashSet<&'a str>| {
+        let mut reached = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(page) = stack.pop() {
+            if orphans.contains(page) && !covered.contains(page) && reached.insert(page) {
+                stack.extend(graph.neighbors(page));
+            }
+        }
+        reached
+    };
+
+    let mut entries = Vec::new();
+    let mut covered = HashSet::new();
+    let no_entry = HashSet::new();
+    for &orphan in orphans {
+        let linked = graph
+            .neighbors_directed(orphan, Direction::Incoming)
+            .any(|page| page != orphan && orphans.contains(page));
+        if !linked {
+            let reached = reach(orphan, &no_entry);
+            entries.push((orphan, reached.len()));
+            covered.extend(reached);
+        }
+    }
+
+    let mut rest: Vec<_> = orphans.difference(&covered).copied().collect();
+    rest.sort_unstable();
+    while !rest.is_empty() {
+        let (entry, reached) = rest
+            .iter()
+            .map(|&page| (page, reach(page, &covered)))
+            .max_by(|a, b| a.1.len().cmp(&b.1.len()).then_with(|| b.0.cmp(a.0)))
+            .unwrap();
+        entries.push((entry, reached.len()));
+        covered.extend(reached);
+        rest.retain(|page| !covered.contains(page));
+    }
+
+    entries.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    entries
+}
+
+/// Group `pages` by their first path segment, e.g. `blog` for `blog/post`.
+/// Pages without a directory are grouped under the empty string.
+pub fn group_by_section<'a>(pages: &[&'a str]) -> BTreeMap<&'a str, Vec<&'a str>> {
+    let mut sections: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for &page in pages {
+        let section = page.split_once('/').map_or("", |(section, _)| section);
+        sections.entry(section).or_default().push(page);
+    }
+    sections
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn groups_orphans_by_section() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["blog/a".to_string(), "shop/x".to_string()],
+        );
+        data.insert("blog/b".to_string(), vec![]);
+        data.insert("blog/c".to_string(), vec!["blog/a".to_string()]);
+        data.insert("shop/y".to_string(), vec![]);
+        data.insert("imprint".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+
+        let mut orphans: Vec<_> = crate::find_orphans(&graph, "index").into_iter().collect();
+        orphans.sort_unstable();
+        let sections = group_by_section(&orphans);
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[""], vec!["imprint"]);
+        assert_eq!(sections["blog"], vec!["blog/b", "blog/c"]);
+        assert_eq!(sections["shop"], vec!["shop/y"]);
+    }
+
+    #[test]
+    fn finds_under_linked_pages() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["a", "b", "c"]));
+        data.insert("a".to_string(), page(&["a", "b", "c"]));
+        data.insert("b".to_string(), page(&["c"]));
+        data.insert("lost".to_string(), page(&["d"]));
+        let graph = make_page_graph(&data);
+
+        // a: 1 (its self-link doesn't count), b: 2, c: 3. Reaching the threshold is enough.
+        assert_eq!(find_under_linked(&graph, "index", 2), vec![("a", 1)]);
+        assert_eq!(
+            find_under_linked(&graph, "index", 3),
+            vec![("a", 1), ("b", 2)]
+        );
+        assert!(find_under_linked(&graph, "index", 1).is_empty());
+    }
+
+    #[test]
+    fn finds_components() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["a", "b"]));
+        data.insert("c".to_string(), page(&["a"]));
+        data.insert("x".to_string(), page(&["y"]));
+        data.insert("lonely".to_string(), page(&[]));
+        let graph = make_page_graph(&data);
+
+        assert_eq!(
+            find_components(&graph),
+            vec![vec!["a", "b", "c", "index"], vec!["x", "y"], vec!["lonely"]]
+        );
+    }
+
+    #[test]
+    fn finds_link_heavy_pages() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["a", "b", "c", "c"]));
+        data.insert("a".to_string(), page(&["a", "b", "c"]));
+        data.insert("b".to_string(), page(&["index", "a", "c", "d"]));
+        let graph = make_page_graph(&data);
+
+        // index: 3, a: 2 without its self-link, b: 4. Reaching the maximum is fine.
+        assert_eq!(find_link_heavy(&graph, 2), vec![("b", 4), ("index", 3)]);
+        assert_eq!(find_link_heavy(&graph, 3), vec![("b", 4)]);
+        assert!(find_link_heavy(&graph, 4).is_empty());
+    }
+
+    #[test]
+    fn sums_inbound_links_by_prefix() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert(
+            "index".to_string(),
+            page(&["blog/2020/a", "blog/2021/b", "shop"]),
+        );
+        data.insert("shop".to_string(), page(&["blog/2020/a", "index"]));
+        let graph = make_page_graph(&data);
+
+        let pair = |prefix: &str, count| (prefix.to_string(), count);
+        assert_eq!(
+            inbound_by_prefix(&graph, 1),
+            vec![pair("blog", 3), pair("index", 1), pair("shop", 1)]
+        );
+        assert_eq!(
+            inbound_by_prefix(&graph, 2),
+            vec![
+                pair("blog/2020", 2),
+                pair("blog/2021", 1),
+                pair("index", 1),
+                pair("shop", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_leaf_pages() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert(
+            "index".to_string(),
+            page(&["thin", "selfish", "rich", "dead"]),
+        );
+        data.insert("thin".to_string(), page(&["index"]));
+        data.insert("selfish".to_string(), page(&["selfish", "index"]));
+        data.insert("rich".to_string(), page(&["index", "thin"]));
+        let graph = make_page_graph(&data);
+
+        assert_eq!(find_leaf_pages(&graph, "index"), vec!["selfish", "thin"]);
+    }
+
+    #[test]
+    fn finds_mutual_links_and_closed_cycles() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["old", "x", "team"]));
+        data.insert("team".to_string(), page(&["about"]));
+        // Pairs linking to each other, with and without other links, and a longer cycle.
+        data.insert("old".to_string(), page(&["new", "old"]));
+        data.insert("new".to_string(), page(&["old"]));
+        data.insert("about".to_string(), page(&["team", "jobs"]));
+        data.insert("x".to_string(), page(&["y"]));
+        data.insert("y".to_string(), page(&["z"]));
+        data.insert("z".to_string(), page(&["x", "y", "index"]));
+        let graph = make_page_graph(&data);
+
+        assert_eq!(
+            find_mutual_links(&graph),
+            vec![("about", "team"), ("new", "old"), ("y", "z")]
+        );
+        assert_eq!(
+            find_closed_cycles(&graph),
+            vec![vec!["index", "x", "y", "z"]]
+        );
+    }
+
+    #[test]
+    fn finds_missing_back_links() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["shop"]));
+        data.insert("shop".to_string(), page(&["shop/trap", "shop/bait"]));
+        data.insert("shop/trap".to_string(), page(&["shop", "shop/trap"]));
+        data.insert("shop/bait".to_string(), page(&["index"]));
+        data.insert("shop/net".to_string(), page(&["shop"]));
+        let graph = make_page_graph(&data);
+        let pairs = Regex::new(r"^shop/[^/]+ -> shop$").unwrap();
+
+        assert_eq!(
+            find_missing_back_links(&graph, &pairs),
+            vec![("shop", "shop/bait"), ("shop/net", "shop")]
+        );
+    }
+
+    #[test]
+    fn counts_links_by_rel() {
+        let pages = vec![(
+            "index".to_string(),
+            r#"<a href="/a"> <a rel="nofollow" href="/b"> <a href="/c" rel="UGC nofollow">
+               <a href="/d" rel="sponsored"> <a href="/d">"#
+                .to_string(),
+        )];
+        let page_graph = crate::build_graph_from_pages(pages, &crate::Config::default());
+
+        assert_eq!(
+            count_by_rel(&page_graph),
+            vec![
+                ("followed", 2),
+                ("nofollow", 2),
+                ("sponsored", 0),
+                ("ugc", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_orphan_entries() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["a"]));
+        // A chain with a branch, and two ways into it.
+        data.insert("old".to_string(), page(&["old/1", "old"]));
+        data.insert("old/1".to_string(), page(&["old/2", "old/3", "index"]));
+        data.insert("older".to_string(), page(&["old/2"]));
+        // A cycle nobody links to, and a lone orphan.
+        data.insert("x".to_string(), page(&["y"]));
+        data.insert("y".to_string(), page(&["x", "z"]));
+        data.insert("lone".to_string(), page(&[]));
+        let graph = make_page_graph(&data);
+
+        let orphans = crate::find_orphans(&graph, "index");
+        assert_eq!(
+            find_orphan_entries(&graph, &orphans),
+            vec![("old", 4), ("x", 3), ("older", 2), ("lone", 1)]
+        );
+    }
+
+    #[test]
+    fn removes_allowed_orphans() {
+        let mut orphans: HashSet<_> = ["thank-you", "legal/archive/2019", "blog/draft", "legal"]
+            .iter()
+            .copied()
+            .collect();
+        let patterns = [
+            Regex::new("^thank-you$").unwrap(),
+            Regex::new("^legal/archive/").unwrap(),
+        ];
+        assert_eq!(remove_allowed_orphans(&mut orphans, &patterns), 2);
+
+        let mut orphans: Vec<_> = orphans.into_iter().collect();
+        orphans.sort_unstable();
+        assert_eq!(orphans, vec!["blog/draft", "legal"]);
+    }
+
+    #[test]
+    fn finds_content_orphans() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        // Every page has "imprint" and "news" in the footer, "news" is also linked from the body of b.
+        data.insert("index".to_string(), page(&["a", "b", "imprint", "news"]));
+        data.insert("a".to_string(), page(&["index", "imprint", "news"]));
+        data.insert("b".to_string(), page(&["index", "imprint", "news", "news"]));
+        data.insert("imprint".to_string(), page(&["index", "imprint", "news"]));
+        data.insert("news".to_string(), page(&["index", "imprint", "news"]));
+
+        assert_eq!(find_content_orphans(&data, "index", 0.8), vec!["imprint"]);
+        assert!(find_content_orphans(&data, "index", 1.1).is_empty());
+    }
+}
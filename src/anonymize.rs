@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use crate::PageGraph;
+
+/// Hex digits kept of each hash, enough to make collisions unlikely on sites of any realistic size.
+const HASH_LENGTH: usize = 12;
+
+/// Replace `name` with a short FNV-1a hash, which is the same on every run and platform.
+/// The fragment of `page#section` is hashed separately, so fragment nodes stay recognizable.
+pub fn hash_name(name: &str) -> String {
+    match name.split_once('#') {
+        Some((page, fragment)) => format!("{}#{}", hash(page), hash(fragment)),
+        None => hash(name),
+    }
+}
+
+fn hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)[..HASH_LENGTH].to_string()
+}
+
+/// Copy `page_graph` with every page name hashed and titles removed,
+/// along with the mapping from hashed names to the original ones.
+pub fn anonymize(page_graph: &PageGraph) -> (PageGraph, BTreeMap<String, String>) {
+    let mut mapping = BTreeMap::new();
+    let mut hashed = |name: &str| {
+        let hashed = hash_name(name);
+        mapping.insert(hashed.clone(), name.to_string());
+        hashed
+    };
+
+    let mut anonymized = PageGraph::default();
+    for (page, targets) in &page_graph.links {
+        let targets = targets.iter().map(|target| hashed(target)).collect();
+        anonymized.links.insert(hashed(page), targets);
+    }
+    for (page, &count) in &page_graph.placeholders {
+        anonymized.placeholders.insert(hashed(page), count);
+    }
+    for (page, &time) in &page_graph.modified {
+        anonymized.modified.insert(hashed(page), time);
+    }
+    for node in &page_graph.external_nodes {
+        anonymized.external_nodes.insert(hashed(node));
+    }
+    (anonymized, mapping)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn hashes_stably() {
+        assert_eq!(hash_name("index"), hash_name("index"));
+        assert_ne!(hash_name("index"), hash_name("team"));
+        assert_eq!(hash_name("index").len(), HASH_LENGTH);
+        assert_eq!(hash_name(""), "cbf29ce48422");
+
+        assert_eq!(
+            hash_name("faq#shipping"),
+            format!("{}#{}", hash_name("faq"), hash_name("shipping"))
+        );
+    }
+
+    #[test]
+    fn keeps_topology() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["a".to_string(), "a#b".to_string()],
+        );
+        data.insert("a".to_string(), vec!["index".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let (anonymized, mapping) = anonymize(&page_graph);
+        let graph = anonymized.graph();
+
+        assert_eq!(mapping.len(), 3);
+        assert_eq!(mapping[&hash_name("a#b")], "a#b");
+        assert_eq!(graph.node_count(), page_graph.graph().node_count());
+        assert_eq!(graph.edge_count(), page_graph.graph().edge_count());
+        let (index, a) = (hash_name("index"), hash_name("a"));
+        assert!(graph.contains_edge(&index, &a));
+        assert!(graph.contains_edge(&a, &index));
+        assert!(graph.contains_edge(&a, &hash_name("a#b")));
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::Context;
+
+use flate2::read::GzDecoder;
+
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use crate::budget::{Budget, Stop};
+use crate::{markdown, node_name, PageSink};
+
+/// Whether `path` looks like an archive `read_archive` can read, judging by its extension.
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+/// Whether the archive entry `name` is a page, i.e. HTML or Markdown.
+fn is_page(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".html") || name.ends_with(".htm") || markdown::is_markdown(&name)
+}
+
+/// Read the HTML and Markdown files of a `.zip`, `.tar`, `.tar.gz` or `.tgz` archive.
+/// Pages are named after their path inside the archive, e.g. `blog/post.html`.
+/// Stops before the next entry once `budget` runs out, telling why; the pages read so far
+/// stay in `pages`.
+pub fn read_archive(
+    path: &Path,
+    pages: &mut dyn PageSink,
+    budget: &Budget,
+) -> Result<Option<Stop>, anyhow::Error> {
+    let file = File::open(path).context(format!("Could not read {}", path.display()))?;
+    let name = path.to_string_lossy().to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        read_zip(file, pages, budget)
+    } else if name.ends_with(".tar") {
+        read_tar(file, pages, budget)
+    } else {
+        read_tar(GzDecoder::new(file), pages, budget)
+    }
+    .context(format!("Could not read archive {}", path.display()))
+}
+
+fn read_zip(
+    reader: impl Read + Seek,
+    pages: &mut dyn PageSink,
+    budget: &Budget,
+) -> Result<Option<Stop>, anyhow::Error> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    for i in 0..archive.len() {
+        if let Some(stop) = budget.check() {
+            return Ok(Some(stop));
+        }
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name()?.to_string();
+        if !entry.is_file() || !is_page(&name) {
+            continue;
+        }
+
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .context(format!("Could not read {}", name))?;
+        pages.push_page(entry_name(&name), content)?;
+    }
+    Ok(None)
+}
+
+fn read_tar(
+    reader: impl Read,
+    pages: &mut dyn PageSink,
+    budget: &Budget,
+) -> Result<Option<Stop>, anyhow::Error> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        if let Some(stop) = budget.check() {
+            return Ok(Some(stop));
+        }
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        if !entry.header().entry_type().is_file() || !is_page(&name) {
+            continue;
+        }
+
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .context(format!("Could not read {}", name))?;
+        pages.push_page(entry_name(&name), content)?;
+    }
+    Ok(None)
+}
+
+/// Strip the `./` tar adds when archiving the current directory, and use slashes.
+fn entry_name(name: &str) -> String {
+    node_name(name).trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    const FILES: &[(&str, &str)] = &[
+        ("index.html", "<a href='/blog/post.html'>"),
+        ("blog/post.html", "<a href='../index.html'>"),
+        ("blog/2021/old.md", "[home](/index.html)"),
+        ("img/logo.png", "not a page"),
+    ];
+
+    fn sorted_names(pages: &[(String, String)]) -> Vec<&str> {
+        let mut names: Vec<_> = pages.iter().map(|(name, _)| name.as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn reads_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("site.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (name, content) in FILES {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let mut pages = Vec::new();
+        let stop = read_archive(&path, &mut pages, &Budget::default()).unwrap();
+
+        assert_eq!(stop, None);
+        assert_eq!(
+            sorted_names(&pages),
+            vec!["blog/2021/old.md", "blog/post.html", "index.html"]
+        );
+
+        let cancelled = Budget::default();
+        cancelled.cancel();
+        let mut pages = Vec::new();
+        let stop = read_archive(&path, &mut pages, &cancelled).unwrap();
+        assert_eq!(stop, Some(Stop::Cancelled));
+        assert!(pages.is_empty());
+    }
+
+    #[test]
+    fn reads_tar_gz() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("site.tar.gz");
+        let gz = flate2::write::GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(gz);
+        for (name, content) in FILES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, format!("./{}", name), content.as_bytes())
+                .unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let mut pages = Vec::new();
+        read_archive(&path, &mut pages, &Budget::default()).unwrap();
+
+        assert_eq!(
+            sorted_names(&pages),
+            vec!["blog/2021/old.md", "blog/post.html", "index.html"]
+        );
+        assert!(is_archive(&path));
+        assert!(!is_archive(Path::new("pages")));
+    }
+}
@@ -0,0 +1,164 @@
+//! Stopping a crawl early, for `--timeout` and Ctrl-C.
+//!
+//! A `Budget` is checked before each page is read and before each page is built into the graph,
+//! so the graph of the pages done so far stays usable when it runs out.
+
+use std::future::{poll_fn, Future};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+use crate::PageSink;
+
+/// Why a `Budget` ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// The deadline passed.
+    TimedOut,
+    /// `Budget::cancel` was called, e.g. on Ctrl-C.
+    Cancelled,
+}
+
+/// When to stop reading pages and building their graph early: at a deadline, or once cancelled.
+/// Clones share the cancellation.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Budget {
+    /// A budget running out `timeout` from now, or only when cancelled if `None`.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self::default().with_timeout(timeout)
+    }
+
+    /// A budget sharing the cancellation of this one, with a deadline `timeout` from now.
+    pub fn with_timeout(&self, timeout: Option<Duration>) -> Self {
+        Self {
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+            ..self.clone()
+        }
+    }
+
+    /// Run out this budget and all its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Why the budget ran out, `None` while it hasn't. Cancellation wins over the deadline.
+    pub fn check(&self) -> Option<Stop> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            Some(Stop::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(Stop::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    /// Wait until the budget runs out. Checks again whenever polled, so polled along with a crawl
+    /// it notices the deadline after each page even while the timer has had no chance to fire.
+    pub async fn exhausted(&self) -> Stop {
+        let notified = self.notify.notified();
+        tokio::pin!(notified);
+        // Registered before the first check, so a cancellation in between isn't missed.
+        notified.as_mut().enable();
+        let mut sleep = self
+            .deadline
+            .map(|deadline| Box::pin(tokio::time::sleep_until(deadline.into())));
+        poll_fn(|cx| {
+            let _ = notified.as_mut().poll(cx);
+            if let Some(sleep) = &mut sleep {
+                if sleep.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(self.check().unwrap_or(Stop::TimedOut));
+                }
+            }
+            self.check().map_or(Poll::Pending, Poll::Ready)
+        })
+        .await
+    }
+}
+
+/// Passes pages on to another `PageSink` until the budget runs out, leaving out the later ones.
+/// Polling a crawl along with `Budget::exhausted` only stops it where it waits, this stops it
+/// after each page either way.
+pub struct Limited<'a> {
+    sink: &'a mut dyn PageSink,
+    budget: &'a Budget,
+    stopped: Option<Stop>,
+}
+
+impl<'a> Limited<'a> {
+    pub fn new(sink: &'a mut dyn PageSink, budget: &'a Budget) -> Self {
+        Self {
+            sink,
+            budget,
+            stopped: None,
+        }
+    }
+
+    /// Why pages were left out, `None` if none were.
+    pub fn stopped(&self) -> Option<Stop> {
+        self.stopped
+    }
+}
+
+impl PageSink for Limited<'_> {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        if self.stopped.is_none() {
+            self.stopped = self.budget.check();
+        }
+        match self.stopped {
+            Some(_) => Ok(()),
+            None => self.sink.push_page(name, content),
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        self.sink.page_count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn limits_pages() {
+        let budget = Budget::new(None);
+        let mut pages = Vec::new();
+        let mut limited = Limited::new(&mut pages, &budget);
+        limited.push_page("a".into(), String::new()).unwrap();
+        budget.cancel();
+        limited.push_page("b".into(), String::new()).unwrap();
+        assert_eq!(limited.stopped(), Some(Stop::Cancelled));
+        assert_eq!(limited.page_count(), 1);
+
+        let expired = Budget::new(Some(Duration::ZERO));
+        let mut pages = Vec::new();
+        let mut limited = Limited::new(&mut pages, &expired);
+        limited.push_page("a".into(), String::new()).unwrap();
+        assert_eq!(limited.stopped(), Some(Stop::TimedOut));
+        assert!(pages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn wakes_on_cancel_and_deadline() {
+        let budget = Budget::new(None);
+        let clone = budget.with_timeout(None);
+        tokio::spawn(async move { clone.cancel() });
+        assert_eq!(budget.exhausted().await, Stop::Cancelled);
+
+        let budget = Budget::new(Some(Duration::from_millis(10)));
+        assert_eq!(budget.exhausted().await, Stop::TimedOut);
+    }
+}
@@ -0,0 +1,187 @@
+//! Findings of the analyses as a stream of events, for `--events-out`.
+
+use petgraph::Direction;
+
+use serde::Serialize;
+
+use std::fmt::Write;
+
+use crate::analysis::{find_closed_cycles, find_mutual_links};
+use crate::{find_unreachable, PageGraph, Reachability};
+
+/// One finding about the site. Serialized as an object with a `kind` and the other fields
+/// that apply, so each line of the stream can be parsed on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    /// What was found: `orphan`, `dead-end`, `self-link`, `cycle`, `broken-link`,
+    /// `external-link` or `broken-anchor`.
+    pub kind: &'static str,
+    /// The page the finding is about, or the link is on.
+    pub page: String,
+    /// The linked page or host, for findings about a link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Further details, like the pages of a cycle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Event {
+    fn new(kind: &'static str, page: &str) -> Self {
+        Self {
+            kind,
+            page: page.to_string(),
+            target: None,
+            detail: None,
+        }
+    }
+}
+
+/// Collect the findings about `page_graph`, with orphans measured from `roots`. Pages without
+/// outgoing links are dead ends, and cycles are pairs of pages linking to each other and larger
+/// groups doing so in cycles, see `find_mutual_links` and `find_closed_cycles`. Broken links are
+/// links to pages which weren't crawled, see `PageGraph::missing_link_targets`. External links and broken anchors are only
+/// known if the graph was built with `Config::include_external` and `Config::check_anchors`.
+/// Grouped by kind in the order above, sorted within each kind.
+pub fn find_events(page_graph: &PageGraph, roots: &[&str]) -> Vec<Event> {
+    let graph = page_graph.graph();
+    let mut events = Vec::new();
+
+    let mut orphans: Vec<_> = find_unreachable(&graph, roots, Reachability::Forward)
+        .into_iter()
+        .collect();
+    orphans.sort_unstable();
+    events.extend(orphans.into_iter().map(|page| Event::new("orphan", page)));
+
+    let mut dead_ends: Vec<_> = graph
+        .nodes()
+        .filter(|&page| {
+            graph
+                .neighbors_directed(page, Direction::Outgoing)
+                .next()
+                .is_none()
+        })
+        .collect();
+    dead_ends.sort_unstable();
+    events.extend(
+        dead_ends
+            .into_iter()
+            .map(|page| Event::new("dead-end", page)),
+    );
+
+    events.extend(page_graph.self_link_pages().into_iter().map(|page| Event {
+        target: Some(page.to_string()),
+        ..Event::new("self-link", page)
+    }));
+
+    let mut cycles: Vec<_> = find_mutual_links(&graph)
+        .into_iter()
+        .map(|(page, other)| vec![page, other])
+        .chain(find_closed_cycles(&graph))
+        .collect();
+    cycles.sort_unstable();
+    events.extend(cycles.into_iter().map(|pages| Event {
+        detail: Some(pages.join(", ")),
+        ..Event::new("cycle", pages[0])
+    }));
+
+    events.extend(
+        page_graph
+            .missing_link_targets()
+            .into_iter()
+            .map(|(page, target)| Event {
+                target: Some(target.to_string()),
+                ..Event::new("broken-link", page)
+            }),
+    );
+
+    let mut external: Vec<_> = page_graph
+        .external_links()
+        .iter()
+        .flat_map(|(page, hosts)| hosts.iter().map(move |host| (page.as_str(), host)))
+        .collect();
+    external.sort_unstable();
+    events.extend(external.into_iter().map(|(page, host)| Event {
+        target: Some(host.clone()),
+        ..Event::new("external-link", page)
+    }));
+
+    events.extend(
+        page_graph
+            .broken_anchors()
+            .into_iter()
+            .map(|(page, target, fragment)| Event {
+                target: Some(format!("{}#{}", target, fragment)),
+                detail: Some(format!("no element with id {:?}", fragment)),
+                ..Event::new("broken-anchor", page)
+            }),
+    );
+    events
+}
+
+/// Write `events` as newline-delimited JSON, one object per line.
+pub fn to_ndjson(events: &[Event]) -> Result<String, anyhow::Error> {
+    let mut ndjson = String::new();
+    for event in events {
+        writeln!(ndjson, "{}", serde_json::to_string(event)?)?;
+    }
+    Ok(ndjson)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_graph_from_pages, Config};
+
+    #[test]
+    fn streams_findings() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r##"<a href="/index"> <a href="/faq#gone"> <a href="https://example.com/x">"##
+                    .to_string(),
+            ),
+            (
+                "faq".to_string(),
+                r#"<a href="/old"> <a href="/missing">"#.to_string(),
+            ),
+            ("old".to_string(), r#"<a href="/new">"#.to_string()),
+            ("new".to_string(), r#"<a href="/old">"#.to_string()),
+            ("lost".to_string(), String::new()),
+        ];
+        let config = Config {
+            include_external: true,
+            check_anchors: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config);
+
+        let events = find_events(&page_graph, &["index"]);
+        let kinds: Vec<_> = events.iter().map(|e| (e.kind, e.page.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("orphan", "lost"),
+                ("dead-end", "lost"),
+                ("dead-end", "missing"),
+                ("self-link", "index"),
+                ("cycle", "new"),
+                ("broken-link", "faq"),
+                ("external-link", "index"),
+                ("broken-anchor", "index"),
+            ]
+        );
+
+        let ndjson = to_ndjson(&events).unwrap();
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), events.len());
+        assert_eq!(lines[4]["detail"], "new, old");
+        assert_eq!(lines[5]["target"], "missing");
+        assert_eq!(lines[6]["target"], "example.com");
+        assert_eq!(lines[7]["target"], "faq#gone");
+        assert!(lines[0].get("target").is_none());
+    }
+}
@@ -0,0 +1,153 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{anchors, markdown, Link};
+
+/// Finds the links in one kind of page, see `Extractors` for choosing it by file extension.
+pub trait LinkExtractor: Send + Sync {
+    /// The links in `content` of the page named `base`, before normalization.
+    fn extract<'a>(&self, content: &'a str, base: &str) -> Vec<Link<'a>>;
+}
+
+/// The `<a href>` links of html, see `anchors`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Html;
+
+impl LinkExtractor for Html {
+    fn extract<'a>(&self, content: &'a str, _base: &str) -> Vec<Link<'a>> {
+        anchors(content).collect()
+    }
+}
+
+/// The link destinations of Markdown, without `rel`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+impl LinkExtractor for Markdown {
+    fn extract<'a>(&self, content: &'a str, _base: &str) -> Vec<Link<'a>> {
+        markdown::links(content)
+            .map(|(href, text)| Link {
+                href,
+                rel: "",
+                text: Cow::Owned(text),
+            })
+            .collect()
+    }
+}
+
+/// Link extractors keyed by lowercase file extension, falling back to `Html` for other pages.
+/// `md` and `markdown` pages use `Markdown` unless registered otherwise.
+#[derive(Clone)]
+pub struct Extractors {
+    by_extension: HashMap<String, Arc<dyn LinkExtractor>>,
+    fallback: Arc<dyn LinkExtractor>,
+}
+
+impl Default for Extractors {
+    fn default() -> Self {
+        let mut extractors = Self {
+            by_extension: HashMap::new(),
+            fallback: Arc::new(Html),
+        };
+        extractors.register("md", Markdown);
+        extractors.register("markdown", Markdown);
+        extractors
+    }
+}
+
+impl fmt::Debug for Extractors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut extensions: Vec<_> = self.by_extension.keys().collect();
+        extensions.sort_unstable();
+        f.debug_struct("Extractors")
+            .field("extensions", &extensions)
+            .finish()
+    }
+}
+
+impl Extractors {
+    /// Use `extractor` for pages ending in `.extension`, replacing the one registered before.
+    pub fn register(&mut self, extension: &str, extractor: impl LinkExtractor + 'static) {
+        self.by_extension
+            .insert(extension.to_ascii_lowercase(), Arc::new(extractor));
+    }
+
+    /// The extractor for the page named `page`, judging by its extension.
+    pub fn for_page(&self, page: &str) -> &dyn LinkExtractor {
+        page.rsplit('/')
+            .next()
+            .and_then(|file| file.rsplit_once('.'))
+            .and_then(|(_, extension)| {
+                self.by_extension
+                    .get(extension.to_ascii_lowercase().as_str())
+            })
+            .unwrap_or(&self.fallback)
+            .as_ref()
+    }
+
+    /// The links in `content` of the page named `page`.
+    pub fn links<'a>(&self, page: &str, content: &'a str) -> Vec<Link<'a>> {
+        self.for_page(page).extract(content, page)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_graph_from_pages, Config};
+
+    /// Every line of a plain text page is a link.
+    struct Lines;
+
+    impl LinkExtractor for Lines {
+        fn extract<'a>(&self, content: &'a str, _base: &str) -> Vec<Link<'a>> {
+            content
+                .lines()
+                .map(|line| Link {
+                    href: Cow::Borrowed(line),
+                    rel: "",
+                    text: Cow::Borrowed(""),
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn dispatches_by_extension() {
+        let mut config = Config::for_domain("example.com");
+        config.extractors.register("TXT", Lines);
+        let pages = vec![
+            ("index".to_string(), r#"<a href="/links.txt">"#.to_string()),
+            ("links.txt".to_string(), "/a\n/b".to_string()),
+            (
+                "notes.md".to_string(),
+                "[a](/a) <a href=\"/c\">".to_string(),
+            ),
+        ];
+
+        let page_graph = build_graph_from_pages(pages, &config);
+
+        let mut edges: Vec<_> = page_graph
+            .graph()
+            .all_edges()
+            .filter(|(_, _, &relation)| relation == "links")
+            .map(|(a, b, _)| (a, b))
+            .collect();
+        edges.sort_unstable();
+        assert_eq!(
+            edges,
+            vec![
+                ("index", "links.txt"),
+                ("links.txt", "a"),
+                ("links.txt", "b"),
+                ("notes.md", "a")
+            ]
+        );
+        assert_eq!(
+            format!("{:?}", config.extractors),
+            r#"Extractors { extensions: ["markdown", "md", "txt"] }"#
+        );
+    }
+}
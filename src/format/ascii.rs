@@ -0,0 +1,216 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use std::collections::HashMap;
+
+use super::RenderOptions;
+use crate::depths;
+use crate::layout::layered;
+
+/// Graphs with more pages are refused unless forced, their drawing wouldn't fit a terminal.
+pub const MAX_NODES: usize = 20;
+
+/// Columns between two boxes of a layer.
+const GAP: usize = 2;
+
+/// Render `graph` as text boxes in layers by depth from the root, ordered as in the `svg` layout.
+/// Links from a page to the next layer are drawn as connectors ending in `v`, all other links
+/// are listed below the drawing.
+pub fn render(
+    graph: &GraphMap<&str, &str, Directed>,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    if graph.node_count() > MAX_NODES && !options.force {
+        anyhow::bail!(
+            "{} pages are too many for an ascii drawing (at most {}), use --force to render it anyway",
+            graph.node_count(),
+            MAX_NODES
+        );
+    }
+
+    let layout = layered(graph, &options.root);
+    let depths = depths(graph, &options.root);
+    let unreachable = depths.values().max().map_or(0, |max| max + 1);
+    let mut layers: Vec<Vec<&str>> = vec![Vec::new(); unreachable + 1];
+    for node in graph.nodes() {
+        let layer = depths.get(node).copied().unwrap_or(unreachable);
+        layers[layer].push(node);
+    }
+    layers.retain(|layer| !layer.is_empty());
+    for layer in &mut layers {
+        layer.sort_by(|a, b| layout.nodes[a].x.total_cmp(&layout.nodes[b].x));
+    }
+
+    let box_width = |node: &str| node.chars().count() + 4;
+    let layer_width = |layer: &[&str]| {
+        layer.iter().map(|node| box_width(node)).sum::<usize>() + GAP * (layer.len() - 1)
+    };
+    let width = layers
+        .iter()
+        .map(|layer| layer_width(layer))
+        .max()
+        .unwrap_or(0);
+
+    // The parents of each layer drawing connectors to the next one, with their children.
+    let is_drawn = |from: &str, to: &str| match (depths.get(from), depths.get(to)) {
+        (Some(from), Some(to)) => from + 1 == *to,
+        _ => false,
+    };
+    let connectors: Vec<Vec<(&str, Vec<&str>)>> = layers
+        .iter()
+        .map(|layer| {
+            layer
+                .iter()
+                .map(|&node| {
+                    let mut children: Vec<_> = graph
+                        .neighbors(node)
+                        .filter(|&child| is_drawn(node, child))
+                        .collect();
+                    children.sort_unstable();
+                    (node, children)
+                })
+                .filter(|(_, children)| !children.is_empty())
+                .collect()
+        })
+        .collect();
+
+    // The last layer has no connectors below it, nor a blank line.
+    let height: usize = connectors
+        .iter()
+        .map(|parents| 3 + parents.len() + 1)
+        .sum::<usize>()
+        .saturating_sub(1);
+    let mut canvas = Canvas {
+        cells: vec![vec![' '; width]; height],
+    };
+    let mut centers = HashMap::new();
+    let mut top = 0;
+    for (layer, parents) in layers.iter().zip(&connectors) {
+        let mut left = (width - layer_width(layer)) / 2;
+        for &node in layer {
+            let inner = box_width(node) - 2;
+            let border = format!("+{}+", "-".repeat(inner));
+            canvas.write(top, left, &border);
+            canvas.write(top + 1, left, &format!("| {} |", node));
+            canvas.write(top + 2, left, &border);
+            centers.insert(node, (top, left + box_width(node) / 2));
+            left += box_width(node) + GAP;
+        }
+        top += 3 + parents.len() + 1;
+    }
+
+    for parents in &connectors {
+        let top = match parents.first() {
+            Some((parent, _)) => centers[parent].0,
+            None => continue,
+        };
+        let arrows = top + 3 + parents.len();
+        for (row, (parent, children)) in (top + 3..).zip(parents) {
+            let column = centers[parent].1;
+            canvas.put(top + 2, column, '+');
+            for row in top + 3..row {
+                canvas.put(row, column, '|');
+            }
+            let columns = || children.iter().map(|child| centers[child].1);
+            let start = columns().chain([column]).min().unwrap();
+            let end = columns().chain([column]).max().unwrap();
+            for column in start..=end {
+                canvas.put(row, column, '-');
+            }
+            for column in columns().chain([column]) {
+                canvas.put(row, column, '+');
+            }
+            for column in columns() {
+                for row in row + 1..arrows {
+                    canvas.put(row, column, '|');
+                }
+                canvas.put(arrows, column, 'v');
+            }
+        }
+    }
+
+    let mut text = String::new();
+    for row in &canvas.cells {
+        let line: String = row.iter().collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    let mut others: Vec<_> = graph
+        .all_edges()
+        .filter(|(from, to, _)| !is_drawn(from, to))
+        .map(|(from, to, _)| format!("  {} -> {}\n", from, to))
+        .collect();
+    others.sort_unstable();
+    if !others.is_empty() {
+        text.push_str("other links:\n");
+        text.extend(others);
+    }
+    Ok(text)
+}
+
+/// A grid of characters the drawing is made on.
+struct Canvas {
+    cells: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    /// Write `text` from `column` on, replacing what was there.
+    fn write(&mut self, row: usize, column: usize, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            self.cells[row][column + i] = c;
+        }
+    }
+
+    /// Draw a connector character, turning crossings into `+`.
+    fn put(&mut self, row: usize, column: usize, c: char) {
+        let cell = &mut self.cells[row][column];
+        *cell = match (*cell, c) {
+            ('|', '-') | ('-', '|') | ('+', _) => '+',
+            _ => c,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+
+    #[test]
+    fn draws_boxes_and_connectors() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["index".to_string()]);
+        let graph = make_page_graph(&data);
+
+        assert_eq!(
+            render(&graph, &RenderOptions::default()).unwrap(),
+            " +-------+
+ | index |
+ +---+---+
+  +--+---+
+  v      v
++---+  +---+
+| a |  | b |
++---+  +---+
+other links:
+  a -> index
+"
+        );
+    }
+
+    #[test]
+    fn refuses_large_graphs() {
+        let pages: Vec<_> = (0..=MAX_NODES).map(|i| format!("page-{}", i)).collect();
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), pages);
+        let graph = make_page_graph(&data);
+
+        assert!(render(&graph, &RenderOptions::default()).is_err());
+        let options = RenderOptions {
+            force: true,
+            ..RenderOptions::default()
+        };
+        assert!(render(&graph, &options).is_ok());
+    }
+}
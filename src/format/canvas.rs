@@ -0,0 +1,112 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use serde::Serialize;
+
+use super::RenderOptions;
+use crate::layout::{layered, NODE_HEIGHT};
+
+/// Obsidian draws text larger than the `svg` layout assumes, so positions are scaled up.
+const SCALE: f64 = 2.0;
+
+/// Top level of an Obsidian `.canvas` file, see <https://jsoncanvas.org>.
+#[derive(Debug, Serialize)]
+struct Canvas<'a> {
+    nodes: Vec<CanvasNode<'a>>,
+    edges: Vec<CanvasEdge<'a>>,
+}
+
+/// A text card for a page, positioned by its top left corner. The id is the page name.
+#[derive(Debug, Serialize)]
+struct CanvasNode<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: &'a str,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+}
+
+/// An arrow between the cards of two pages, `label`led `contains` for fragments.
+#[derive(Debug, Serialize)]
+struct CanvasEdge<'a> {
+    id: String,
+    #[serde(rename = "fromNode")]
+    from_node: &'a str,
+    #[serde(rename = "toNode")]
+    to_node: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+}
+
+/// Render `graph` as an Obsidian canvas, with the cards placed like the `svg` layout.
+pub fn render(
+    graph: &GraphMap<&str, &str, Directed>,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    let layout = layered(graph, &options.root);
+
+    let mut nodes: Vec<_> = layout
+        .nodes
+        .iter()
+        .map(|(&name, node)| CanvasNode {
+            id: name,
+            kind: "text",
+            text: name,
+            x: ((node.x - node.width / 2.0) * SCALE).round() as i64,
+            y: ((node.y - NODE_HEIGHT / 2.0) * SCALE).round() as i64,
+            width: (node.width * SCALE).round() as i64,
+            height: (NODE_HEIGHT * SCALE).round() as i64,
+        })
+        .collect();
+    nodes.sort_unstable_by_key(|node| node.id);
+
+    let mut edges: Vec<_> = graph
+        .all_edges()
+        .map(|(from, to, &relation)| CanvasEdge {
+            id: format!("{}->{}", from, to),
+            from_node: from,
+            to_node: to,
+            label: (relation == "contains").then_some(relation),
+        })
+        .collect();
+    edges.sort_unstable_by_key(|edge| (edge.from_node, edge.to_node));
+
+    Ok(serde_json::to_string_pretty(&Canvas { nodes, edges })?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_canvas_json() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["b".to_string()]);
+        let graph = make_page_graph(&data);
+
+        let canvas = render(&graph, &RenderOptions::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&canvas).unwrap();
+
+        let nodes = json["nodes"].as_array().unwrap();
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges.len(), 3);
+        let ids: Vec<_> = nodes.iter().map(|n| n["id"].as_str().unwrap()).collect();
+        for node in nodes {
+            assert_eq!(node["type"], "text");
+            for key in ["x", "y", "width", "height"].iter() {
+                assert!(node[key].is_i64());
+            }
+        }
+        for edge in edges {
+            assert!(ids.contains(&edge["fromNode"].as_str().unwrap()));
+            assert!(ids.contains(&edge["toNode"].as_str().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use std::fmt::Write;
+
+/// Names or links passed to one `UNWIND`, keeping statements small enough for the server.
+const BATCH_SIZE: usize = 1000;
+
+/// Render `graph` as Cypher statements creating `(:Page {name})` nodes, `[:LINKS_TO]` links and
+/// `[:CONTAINS]` relationships to fragment nodes. Statements `MERGE` in batches using `UNWIND`,
+/// so importing the output twice doesn't duplicate anything.
+pub fn render(graph: &GraphMap<&str, &str, Directed>) -> Result<String, anyhow::Error> {
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+
+    let mut cypher =
+        String::from("CREATE CONSTRAINT IF NOT EXISTS FOR (p:Page) REQUIRE p.name IS UNIQUE;\n");
+    for batch in nodes.chunks(BATCH_SIZE) {
+        let names: Vec<_> = batch.iter().map(|name| escape(name)).collect();
+        writeln!(
+            cypher,
+            "UNWIND [{}] AS name\nMERGE (:Page {{name: name}});",
+            names.join(", ")
+        )?;
+    }
+
+    for (label, relationship) in &[("links", "LINKS_TO"), ("contains", "CONTAINS")] {
+        let mut edges: Vec<_> = graph
+            .all_edges()
+            .filter(|(_, _, weight)| *weight == label)
+            .map(|(from, to, _)| format!("[{}, {}]", escape(from), escape(to)))
+            .collect();
+        edges.sort_unstable();
+
+        for batch in edges.chunks(BATCH_SIZE) {
+            writeln!(
+                cypher,
+                "UNWIND [{}] AS link\n\
+                 MATCH (from:Page {{name: link[0]}}), (to:Page {{name: link[1]}})\n\
+                 MERGE (from)-[:{}]->(to);",
+                batch.join(", "),
+                relationship
+            )?;
+        }
+    }
+    Ok(cypher)
+}
+
+/// Quote `text` as a Cypher string literal.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04X}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PageGraph;
+    use regex::Regex;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_merges() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["say \"hi\"".to_string(), "faq#top".to_string()],
+        );
+        data.insert("faq".to_string(), vec!["index".to_string()]);
+        let page_graph = PageGraph::from(data);
+        let cypher = render(&page_graph.graph()).unwrap();
+
+        let string = r#""(?:[^"\\]|\\.)*""#;
+        let list = |item: &str| format!(r"^UNWIND \[({item}(?:, {item})*)\] AS", item = item);
+        let nodes = Regex::new(&list(string)).unwrap();
+        let pair = format!(r"\[{s}, {s}\]", s = string);
+        let links = Regex::new(&list(&pair)).unwrap();
+        let strings = Regex::new(string).unwrap();
+
+        let statements: Vec<_> = cypher.split(";\n").filter(|s| !s.is_empty()).collect();
+        assert_eq!(statements.len(), 4);
+        let node_count: usize = statements
+            .iter()
+            .filter_map(|s| nodes.captures(s))
+            .map(|c| strings.find_iter(&c[1]).count())
+            .sum();
+        let link_count: usize = statements
+            .iter()
+            .filter_map(|s| links.captures(s))
+            .map(|c| strings.find_iter(&c[1]).count() / 2)
+            .sum();
+        assert_eq!(node_count, 4);
+        assert_eq!(link_count, 4);
+        assert!(cypher.contains(r#""say \"hi\"""#));
+        assert!(cypher.contains("MERGE (from)-[:CONTAINS]->(to)"));
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use super::escape_xml;
+use crate::PageGraph;
+
+/// Render `page_graph` as DGML for the graph viewer of Visual Studio: a `<Node>` per page with
+/// its name as `Id` and its title as `Label`, and a `<Link>` per link. Edges from a page to its
+/// fragments are in the `Contains` category, which groups the fragments into the page.
+pub fn render(page_graph: &PageGraph) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+    let mut edges: Vec<_> = graph.all_edges().collect();
+    edges.sort_unstable();
+
+    let mut dgml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <DirectedGraph xmlns=\"http://schemas.microsoft.com/vs/2009/dgml\">\n  <Nodes>\n",
+    );
+    for node in nodes {
+        let label = page_graph.title(node).unwrap_or(node);
+        writeln!(
+            dgml,
+            "    <Node Id=\"{}\" Label=\"{}\" />",
+            escape_xml(node),
+            escape_xml(label)
+        )?;
+    }
+    dgml.push_str("  </Nodes>\n  <Links>\n");
+    for (source, target, &relation) in edges {
+        let category = match relation {
+            "contains" => " Category=\"Contains\"",
+            _ => "",
+        };
+        writeln!(
+            dgml,
+            "    <Link Source=\"{}\" Target=\"{}\"{} />",
+            escape_xml(source),
+            escape_xml(target),
+            category
+        )?;
+    }
+    dgml.push_str("  </Links>\n</DirectedGraph>\n");
+    Ok(dgml)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_directed_graph() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["a&b".to_string(), "c#top".to_string()],
+        );
+        data.insert("c".to_string(), vec!["index".to_string()]);
+        let mut titles = HashMap::new();
+        titles.insert("index".to_string(), "<Home>".to_string());
+        let page_graph = PageGraph::from(data).with_titles(titles);
+
+        let dgml = render(&page_graph).unwrap();
+
+        assert!(dgml.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<DirectedGraph "));
+        assert!(dgml.contains("xmlns=\"http://schemas.microsoft.com/vs/2009/dgml\""));
+        assert!(dgml.ends_with("</DirectedGraph>\n"));
+        assert_eq!(dgml.matches("<Node ").count(), 4);
+        assert_eq!(dgml.matches("<Link ").count(), 4);
+        assert!(dgml.contains("<Node Id=\"index\" Label=\"&lt;Home&gt;\" />"));
+        assert!(dgml.contains("<Link Source=\"index\" Target=\"a&amp;b\" />"));
+        assert!(dgml.contains("<Link Source=\"c\" Target=\"c#top\" Category=\"Contains\" />"));
+    }
+}
@@ -0,0 +1,547 @@
+use lazy_static::lazy_static;
+
+use petgraph::dot::{Config, Dot};
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use regex::Regex;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::RenderOptions;
+use crate::PageGraph;
+
+lazy_static! {
+    static ref KEY: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+}
+
+/// A graph attribute for the DOT header, e.g. `rankdir=LR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub key: String,
+    pub value: String,
+}
+
+impl Attribute {
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl FromStr for Attribute {
+    type Err = String;
+
+    /// Parse `key=value`. Keys must be identifiers, values can't span lines;
+    /// values are quoted when rendered, so they can't end the attribute.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got {}", s))?;
+        let (key, value) = (key.trim(), value.trim());
+        if !KEY.is_match(key) {
+            return Err(format!("invalid attribute name {:?}", key));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(format!("attribute {} can't contain line breaks", key));
+        }
+        Ok(Attribute::new(key, value))
+    }
+}
+
+/// What makes edges thicker with `--weight-source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightSource {
+    /// How often the page links to the other.
+    Count,
+    /// How often visitors went from the page to the other, see `RenderOptions::traffic`.
+    Traffic,
+}
+
+impl WeightSource {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &["count", "traffic"];
+}
+
+impl FromStr for WeightSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "count" => Ok(WeightSource::Count),
+            "traffic" => Ok(WeightSource::Traffic),
+            other => Err(format!(
+                "unknown weight source {}, expected one of {}",
+                other,
+                WeightSource::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Colors of pages by depth, see `RenderOptions::depth_palette`: the first for the root and the
+/// last for the deepest pages, blended in between. Parsed from `#rrggbb` colors separated by
+/// commas, green over yellow to red by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette(Vec<[u8; 3]>);
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette(vec![
+            [0x1a, 0x98, 0x50],
+            [0xfe, 0xe0, 0x8b],
+            [0xd7, 0x30, 0x27],
+        ])
+    }
+}
+
+impl Palette {
+    /// The color `at` the given fraction of the way from the first color to the last.
+    pub fn color(&self, at: f64) -> String {
+        let last = self.0.len() - 1;
+        let position = at.clamp(0.0, 1.0) * last as f64;
+        let i = (position.floor() as usize).min(last.saturating_sub(1));
+        let (from, to) = (self.0[i], self.0[(i + 1).min(last)]);
+        let blend = position - i as f64;
+        let channel = |c: usize| {
+            let (from, to) = (f64::from(from[c]), f64::from(to[c]));
+            (from + (to - from) * blend).round() as u8
+        };
+        format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |color: &str| {
+            let color = color.trim();
+            let hex = color
+                .strip_prefix('#')
+                .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or_else(|| format!("invalid color {:?}, expected #rrggbb", color))?;
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+            Ok([channel(0), channel(2), channel(4)])
+        };
+        let colors = s
+            .split(',')
+            .map(parse)
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Palette(colors))
+    }
+}
+
+/// Fill of pages the root can't reach when coloring by depth.
+const UNREACHABLE_COLOR: &str = "#c0c0c0";
+
+/// Most rows of the depth legend, deeper sites get rows for several depths.
+const DEPTH_LEGEND_ROWS: usize = 5;
+
+/// `penwidth` of the lightest and heaviest edges.
+const PENWIDTHS: (f64, f64) = (1.0, 8.0);
+
+/// Directions accepted by `--rankdir`.
+pub const RANKDIRS: &[&str] = &["TB", "LR", "BT", "RL"];
+
+/// Render `page_graph` as Graphviz DOT. Edges carry their `options.traffic`, pages last modified
+/// before `options.stale_before` are red, other hosts are dashed boxes, and
+/// `options.dot_attributes` go into the header. With `options.weight_source`, edges get thicker
+/// the heavier they are, explained by a legend. Links are labelled with `options.edge_label`,
+/// they have no label without it. With `options.depth_palette`, pages are filled by their
+/// depth from `options.root`, explained by a legend too. With `options.tooltips`, pages show
+/// their degrees, depth and title on hover in SVG.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let counts = page_graph.link_counts();
+    let weight = |from: &str, to: &str| match options.weight_source {
+        Some(WeightSource::Count) => counts.get(&(from, to)).copied().unwrap_or(1),
+        Some(WeightSource::Traffic) | None => {
+            let key = (from.to_string(), to.to_string());
+            options.traffic.get(&key).copied().unwrap_or(0)
+        }
+    };
+    let weights: Vec<_> = graph.all_edges().map(|(a, b, _)| weight(a, b)).collect();
+    let range = (
+        weights.iter().copied().min().unwrap_or(0),
+        weights.iter().copied().max().unwrap_or(0),
+    );
+    let traffic = |_, (from, to, &relation): (&str, &str, &&str)| {
+        let key = (from.to_string(), to.to_string());
+        let mut attributes = Vec::new();
+        if let (Some(label), "links") = (&options.edge_label, relation) {
+            attributes.push(format!("label = {}", quote(label)));
+        }
+        if let Some(count) = options.traffic.get(&key) {
+            attributes.push(format!("traffic = {}", count));
+        }
+        if options.weight_source.is_some() {
+            let penwidth = penwidth(weight(from, to), range);
+            attributes.push(format!("penwidth = {:.1}", penwidth));
+        }
+        attributes.join(", ")
+    };
+    let depths = if options.depth_palette.is_some() || options.tooltips {
+        crate::depths(&graph, &options.root)
+    } else {
+        HashMap::new()
+    };
+    let max_depth = depths.values().copied().max().unwrap_or(0);
+    let node = |_, (name, _): (&str, _)| {
+        let mut attributes = Vec::new();
+        if options.tooltips {
+            attributes.push(format!(
+                "tooltip = {}",
+                tooltip(page_graph, &graph, &depths, name)
+            ));
+        }
+        if page_graph.is_external(name) {
+            attributes.push("shape = box, style = dashed".to_string());
+            return attributes.join(", ");
+        }
+        let modified = page_graph.modified(name);
+        if let (Some(modified), Some(before)) = (modified, options.stale_before) {
+            if modified < before {
+                attributes.push("color = red, fontcolor = red".to_string());
+            }
+        }
+        if let Some(palette) = &options.depth_palette {
+            let fill = match depths.get(name) {
+                Some(&depth) => depth_color(palette, depth, max_depth),
+                None => UNREACHABLE_COLOR.to_string(),
+            };
+            attributes.push(format!("style = filled, fillcolor = {}", quote(&fill)));
+        }
+        attributes.join(", ")
+    };
+    let dot = format!(
+        "{:?}",
+        Dot::with_attr_getters(&graph, &[Config::EdgeNoLabel], &traffic, &node)
+    );
+
+    let mut header = String::new();
+    for attribute in &options.dot_attributes {
+        header.push_str(&format!(
+            "    {} = {}\n",
+            attribute.key,
+            quote(&attribute.value)
+        ));
+    }
+    // Right after the opening `digraph {` line.
+    let body = dot.find('\n').map_or(dot.len(), |i| i + 1);
+    let mut dot = format!("{}{}{}", &dot[..body], header, &dot[body..]);
+    if let Some(source) = options.weight_source {
+        // Before the closing `}`.
+        let end = dot.trim_end().len() - 1;
+        dot.insert_str(end, &legend(source, range));
+    }
+    if let Some(palette) = &options.depth_palette {
+        let unreachable = depths.len() < graph.node_count();
+        let end = dot.trim_end().len() - 1;
+        dot.insert_str(end, &depth_legend(palette, max_depth, unreachable));
+    }
+    Ok(dot)
+}
+
+/// Quote `value` as a DOT string.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+/// Escape `value` for a DOT string, so it can't end it.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The quoted tooltip of `page` like `indeg=3 outdeg=5 depth=2`, with the title of the page
+/// on a second line if it has one. Pages the root can't reach have `depth=none`.
+fn tooltip(
+    page_graph: &PageGraph,
+    graph: &GraphMap<&str, &str, Directed>,
+    depths: &HashMap<&str, usize>,
+    page: &str,
+) -> String {
+    let depth = depths
+        .get(page)
+        .map_or_else(|| "none".to_string(), usize::to_string);
+    let mut tooltip = format!(
+        "indeg={} outdeg={} depth={}",
+        graph.neighbors_directed(page, Direction::Incoming).count(),
+        graph.neighbors_directed(page, Direction::Outgoing).count(),
+        depth
+    );
+    if let Some(title) = page_graph.title(page) {
+        // A line break in Graphviz's escString, not escaped itself.
+        tooltip.push_str("\\n");
+        tooltip.push_str(&escape(title));
+    }
+    format!("\"{}\"", tooltip)
+}
+
+/// The `penwidth` of an edge of `weight`, scaled from the lightest to the heaviest in `range`.
+fn penwidth(weight: usize, (min, max): (usize, usize)) -> f64 {
+    let (thin, thick) = PENWIDTHS;
+    if max == min {
+        return thin;
+    }
+    thin + (thick - thin) * (weight - min) as f64 / (max - min) as f64
+}
+
+/// A cluster of three edges, as thin, medium and thick as edges get, labelled with their weight.
+fn legend(source: WeightSource, (min, max): (usize, usize)) -> String {
+    let label = match source {
+        WeightSource::Count => "links",
+        WeightSource::Traffic => "navigations",
+    };
+    let mut legend = format!(
+        "    subgraph cluster_legend {{\n        label = \"{}\"\n        node [shape = point, label = \"\"]\n",
+        label
+    );
+    let mut weights = vec![min, min + (max - min) / 2, max];
+    weights.dedup();
+    for (i, weight) in weights.into_iter().enumerate() {
+        legend.push_str(&format!(
+            "        legend_{} -> legend_{} [penwidth = {:.1}, label = \"{}\"]\n",
+            2 * i,
+            2 * i + 1,
+            penwidth(weight, (min, max)),
+            weight
+        ));
+    }
+    legend.push_str("    }\n");
+    legend
+}
+
+/// The fill of pages at `depth` on a site `max_depth` clicks deep.
+fn depth_color(palette: &Palette, depth: usize, max_depth: usize) -> String {
+    if max_depth == 0 {
+        return palette.color(0.0);
+    }
+    palette.color(depth as f64 / max_depth as f64)
+}
+
+/// A cluster of filled nodes labelled with the depths they stand for, like `0`, `1-2` and
+/// `unreachable`.
+fn depth_legend(palette: &Palette, max_depth: usize, unreachable: bool) -> String {
+    let mut legend = String::from(
+        "    subgraph cluster_depth_legend {\n        label = \"depth\"\n        node [shape = box, style = filled]\n",
+    );
+    let depths_per_row = (max_depth + DEPTH_LEGEND_ROWS) / DEPTH_LEGEND_ROWS;
+    for (i, first) in (0..=max_depth).step_by(depths_per_row).enumerate() {
+        let last = (first + depths_per_row - 1).min(max_depth);
+        let label = if first == last {
+            first.to_string()
+        } else {
+            format!("{}-{}", first, last)
+        };
+        legend.push_str(&format!(
+            "        depth_legend_{} [label = \"{}\", fillcolor = {}]\n",
+            i,
+            label,
+            quote(&depth_color(palette, first, max_depth))
+        ));
+    }
+    if unreachable {
+        legend.push_str(&format!(
+            "        depth_legend_unreachable [label = \"unreachable\", fillcolor = {}]\n",
+            quote(UNREACHABLE_COLOR)
+        ));
+    }
+    legend.push_str("    }\n");
+    legend
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn adds_header_attributes() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            dot_attributes: vec![
+                Attribute::new("rankdir", "LR"),
+                "nodesep = 0.5".parse().unwrap(),
+                r#"label=say "hi""#.parse().unwrap(),
+            ],
+            ..RenderOptions::default()
+        };
+        let dot = render(&page_graph, &options).unwrap();
+
+        assert!(dot.starts_with(
+            "digraph {\n    rankdir = \"LR\"\n    nodesep = \"0.5\"\n    label = \"say \\\"hi\\\"\"\n    0 ["
+        ));
+    }
+
+    #[test]
+    fn traffic_on_edges() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let mut options = RenderOptions::default();
+        let plain = render(&page_graph, &options).unwrap();
+        assert!(!plain.contains("traffic"));
+
+        options
+            .traffic
+            .insert(("index".to_string(), "b".to_string()), 7);
+        let dot = render(&page_graph, &options).unwrap();
+        assert_eq!(dot.matches("traffic = 7").count(), 1);
+        assert_eq!(dot.lines().count(), plain.lines().count());
+    }
+
+    #[test]
+    fn scales_penwidth_by_weight() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["a", "a", "a", "b"]));
+        data.insert("a".to_string(), page(&["b", "b"]));
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            weight_source: Some(WeightSource::Count),
+            ..RenderOptions::default()
+        };
+        let dot = render(&page_graph, &options).unwrap();
+        // Edges of 3, 1 and 2 links, and the legend.
+        assert_eq!(dot.matches("penwidth = 8.0").count(), 2);
+        assert_eq!(dot.matches("penwidth = 4.5").count(), 2);
+        assert_eq!(dot.matches("penwidth = 1.0").count(), 2);
+        assert!(dot.contains("label = \"links\""));
+        assert!(dot.trim_end().ends_with("    }\n}"));
+
+        let plain = render(&page_graph, &RenderOptions::default()).unwrap();
+        assert!(!plain.contains("penwidth"));
+    }
+
+    #[test]
+    fn labels_links() {
+        let pages = vec![("index".to_string(), r#"<a href="/a#x">"#.to_string())];
+        let config = crate::Config {
+            track_fragments: true,
+            ..crate::Config::default()
+        };
+        let page_graph = crate::build_graph_from_pages(pages, &config);
+
+        let plain = render(&page_graph, &RenderOptions::default()).unwrap();
+        assert!(plain.contains("0 -> 1 [ ]"));
+
+        let options = RenderOptions {
+            edge_label: Some("links to \"a\"".to_string()),
+            ..RenderOptions::default()
+        };
+        let dot = render(&page_graph, &options).unwrap();
+        // Only the link, not the edge from `a` to its fragment.
+        assert!(dot.contains("0 -> 1 [ label = \"links to \\\"a\\\"\"]"));
+        assert!(dot.contains("2 -> 1 [ ]"));
+    }
+
+    #[test]
+    fn colors_by_depth() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        data.insert("a".to_string(), vec!["b".to_string()]);
+        data.insert("lost".to_string(), vec![]);
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            depth_palette: Some("#00ff00,#ff0000".parse().unwrap()),
+            ..RenderOptions::default()
+        };
+        let dot = render(&page_graph, &options).unwrap();
+
+        let fill = |page: &str| {
+            let line = dot
+                .lines()
+                .find(|line| line.contains(&format!("label = \"\\\"{}\\\"\"", page)))
+                .unwrap();
+            let (_, fill) = line.split_once("fillcolor = ").unwrap();
+            fill[1..8].to_string()
+        };
+        assert_eq!(fill("index"), "#00ff00");
+        assert_eq!(fill("a"), "#808000");
+        assert_eq!(fill("b"), "#ff0000");
+        assert_eq!(fill("lost"), UNREACHABLE_COLOR);
+        assert!(dot.contains("subgraph cluster_depth_legend"));
+        assert!(dot.contains("[label = \"2\", fillcolor = \"#ff0000\"]"));
+        assert!(dot.contains("[label = \"unreachable\""));
+
+        let plain = render(&page_graph, &RenderOptions::default()).unwrap();
+        assert!(!plain.contains("fillcolor"));
+    }
+
+    #[test]
+    fn adds_tooltips() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["b".to_string()]);
+        data.insert("lost".to_string(), vec!["a".to_string()]);
+        let mut titles = HashMap::new();
+        titles.insert("a".to_string(), "Say \"hi\"".to_string());
+        let page_graph = PageGraph::from(data).with_titles(titles);
+
+        let options = RenderOptions {
+            tooltips: true,
+            ..RenderOptions::default()
+        };
+        let dot = render(&page_graph, &options).unwrap();
+
+        let tooltips: Vec<_> = dot
+            .lines()
+            .filter_map(|line| line.split_once("tooltip = \"").map(|(_, rest)| rest))
+            .map(|rest| &rest[..rest.rfind("\"]").unwrap()])
+            .collect();
+        assert_eq!(tooltips.len(), 4);
+        let format = Regex::new(r"^indeg=\d+ outdeg=\d+ depth=(\d+|none)(\\n.*)?$").unwrap();
+        assert!(tooltips.iter().all(|tooltip| format.is_match(tooltip)));
+        assert!(tooltips.contains(&"indeg=0 outdeg=2 depth=0"));
+        assert!(tooltips.contains(&r#"indeg=2 outdeg=1 depth=1\nSay \"hi\""#));
+        assert!(tooltips.contains(&"indeg=2 outdeg=0 depth=1"));
+        assert!(tooltips.contains(&"indeg=0 outdeg=1 depth=none"));
+
+        let plain = render(&page_graph, &RenderOptions::default()).unwrap();
+        assert!(!plain.contains("tooltip"));
+    }
+
+    #[test]
+    fn parses_palettes() {
+        let palette: Palette = "#000000, #FFFFFF".parse().unwrap();
+        assert_eq!(palette.color(0.5), "#808080");
+        assert_eq!(Palette::default().color(0.0), "#1a9850");
+        assert!("green,red".parse::<Palette>().is_err());
+        assert!("#12345".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn styles_external_nodes() {
+        let pages = vec![(
+            "index".to_string(),
+            r#"<a href="/a"> <a href="https://github.com/">"#.to_string(),
+        )];
+        let config = crate::Config {
+            include_external: true,
+            ..crate::Config::default()
+        };
+        let page_graph = crate::build_graph_from_pages(pages, &config).with_external_nodes();
+
+        let dot = render(&page_graph, &RenderOptions::default()).unwrap();
+        let dashed: Vec<_> = dot.lines().filter(|l| l.contains("dashed")).collect();
+        assert_eq!(dashed.len(), 1);
+        assert!(dashed[0].contains("github.com"));
+    }
+
+    #[test]
+    fn rejects_unsafe_attributes() {
+        assert!("rankdir".parse::<Attribute>().is_err());
+        assert!("rank dir=LR".parse::<Attribute>().is_err());
+        assert!("a]; b=c".parse::<Attribute>().is_err());
+        assert!("label=a\nb".parse::<Attribute>().is_err());
+    }
+}
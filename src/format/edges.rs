@@ -0,0 +1,80 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use std::fmt::Write;
+
+use super::RenderOptions;
+
+/// Render one `source target` line per link, sorted, for `awk` and `cut`.
+/// With `options.isolated`, pages without any links follow, alone on their line.
+/// Page names containing whitespace would be ambiguous, so they are an error.
+pub fn render(
+    graph: &GraphMap<&str, &str, Directed>,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    if let Some(name) = graph
+        .nodes()
+        .find(|name| name.contains(char::is_whitespace))
+    {
+        anyhow::bail!(
+            "page name {:?} contains whitespace, which the edges format can't represent; use nodes-csv or json",
+            name
+        );
+    }
+
+    let mut edges: Vec<_> = graph.all_edges().map(|(a, b, _)| (a, b)).collect();
+    edges.sort_unstable();
+
+    let mut plain = String::new();
+    for (source, target) in edges {
+        writeln!(plain, "{} {}", source, target)?;
+    }
+    if options.isolated {
+        let mut isolated: Vec<_> = graph
+            .nodes()
+            .filter(|&node| {
+                graph.neighbors(node).next().is_none()
+                    && graph
+                        .neighbors_directed(node, Direction::Incoming)
+                        .next()
+                        .is_none()
+            })
+            .collect();
+        isolated.sort_unstable();
+        for node in isolated {
+            writeln!(plain, "{}", node)?;
+        }
+    }
+    Ok(plain)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_pairs() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["b".to_string(), "a".to_string()]);
+        data.insert("a".to_string(), vec!["index".to_string()]);
+        data.insert("lonely".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+
+        let mut options = RenderOptions::default();
+        assert_eq!(
+            render(&graph, &options).unwrap(),
+            "a index\nindex a\nindex b\n"
+        );
+        options.isolated = true;
+        assert_eq!(
+            render(&graph, &options).unwrap(),
+            "a index\nindex a\nindex b\nlonely\n"
+        );
+
+        data.insert("a b".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+        assert!(render(&graph, &options).is_err());
+    }
+}
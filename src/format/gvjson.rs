@@ -0,0 +1,109 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+/// Top level of a Graphviz JSON document.
+#[derive(Debug, Serialize)]
+struct GvGraph<'a> {
+    name: &'a str,
+    directed: bool,
+    strict: bool,
+    #[serde(rename = "_subgraph_cnt")]
+    subgraph_count: usize,
+    objects: Vec<GvObject<'a>>,
+    edges: Vec<GvEdge>,
+}
+
+/// A node, identified by its `_gvid`.
+#[derive(Debug, Serialize)]
+struct GvObject<'a> {
+    #[serde(rename = "_gvid")]
+    id: usize,
+    name: &'a str,
+}
+
+/// An edge, `tail` and `head` refer to the `_gvid` of the objects.
+#[derive(Debug, Serialize)]
+struct GvEdge {
+    #[serde(rename = "_gvid")]
+    id: usize,
+    tail: usize,
+    head: usize,
+}
+
+/// Render `graph` like `dot -Tjson0` would, without layout information.
+pub fn render(graph: &GraphMap<&str, &str, Directed>) -> Result<String, anyhow::Error> {
+    let ids: HashMap<&str, usize> = graph.nodes().enumerate().map(|(i, n)| (n, i)).collect();
+
+    let objects = graph
+        .nodes()
+        .enumerate()
+        .map(|(id, name)| GvObject { id, name })
+        .collect();
+
+    let edges = graph
+        .all_edges()
+        .enumerate()
+        .map(|(id, (tail, head, _))| GvEdge {
+            id,
+            tail: ids[tail],
+            head: ids[head],
+        })
+        .collect();
+
+    let document = GvGraph {
+        name: "page-graph",
+        directed: true,
+        strict: false,
+        subgraph_count: 0,
+        objects,
+        edges,
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+
+    #[test]
+    fn renders_parseable_gvjson() {
+        let mut data = HashMap::new();
+        data.insert("a".to_string(), vec!["b".to_string(), "c".to_string()]);
+        data.insert("b".to_string(), vec!["c".to_string()]);
+        let graph = make_page_graph(&data);
+
+        let json: serde_json::Value = serde_json::from_str(&render(&graph).unwrap()).unwrap();
+
+        assert_eq!(json["directed"], true);
+        let objects = json["objects"].as_array().unwrap();
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(objects.len(), 3);
+        assert_eq!(edges.len(), 3);
+
+        let name_of = |id: &serde_json::Value| {
+            objects
+                .iter()
+                .find(|o| &o["_gvid"] == id)
+                .map(|o| o["name"].as_str().unwrap().to_string())
+                .unwrap()
+        };
+        let mut pairs: Vec<_> = edges
+            .iter()
+            .map(|e| (name_of(&e["tail"]), name_of(&e["head"])))
+            .collect();
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("a".to_string(), "c".to_string()),
+                ("b".to_string(), "c".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::RenderOptions;
+use crate::stats::GraphStats;
+use crate::PageGraph;
+
+/// Measurement name of `influx` output, unless configured otherwise.
+pub const DEFAULT_MEASUREMENT: &str = "page_graph";
+
+/// A tag for `influx` output, e.g. `site=www`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Tag {
+    type Err = String;
+
+    /// Parse `key=value`, splitting at the first `=`. Neither can be empty or span lines,
+    /// line protocol has no way to write those.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got {}", s))?;
+        if key.is_empty() || value.is_empty() {
+            return Err(format!("tag keys and values can't be empty, got {}", s));
+        }
+        if s.contains(['\n', '\r']) {
+            return Err(format!("tag {} can't contain line breaks", key));
+        }
+        Ok(Tag {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Render the `GraphStats` of `page_graph` as one line of InfluxDB line protocol, with
+/// `options.influx_measurement`, the `options.influx_tags` sorted by key and a timestamp in
+/// nanoseconds. `max_depth` is left out if the root is missing.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let measurement = &options.influx_measurement;
+    if measurement.is_empty() || measurement.contains(['\n', '\r']) {
+        anyhow::bail!("invalid measurement name {:?}", measurement);
+    }
+    let graph = page_graph.graph();
+    let stats = GraphStats::new(&graph, &options.all_roots());
+    let timestamp = options
+        .timestamp
+        .unwrap_or_else(SystemTime::now)
+        .duration_since(UNIX_EPOCH)?
+        .as_nanos();
+
+    let mut line = escape(measurement, &[',', ' ']);
+    let mut tags: Vec<_> = options.influx_tags.iter().collect();
+    tags.sort_by(|a, b| a.key.cmp(&b.key));
+    for tag in tags {
+        let special = [',', '=', ' '];
+        write!(
+            line,
+            ",{}={}",
+            escape(&tag.key, &special),
+            escape(&tag.value, &special)
+        )?;
+    }
+
+    let mut fields = vec![
+        ("node_count", stats.node_count),
+        ("edge_count", stats.edge_count),
+        ("orphan_count", stats.orphan_count),
+        ("dead_end_count", stats.dead_end_count),
+        ("component_count", stats.component_count),
+    ];
+    fields.extend(stats.max_depth.map(|depth| ("max_depth", depth)));
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}i", key, value))
+        .collect();
+    writeln!(line, " {} {}", fields.join(","), timestamp)?;
+    Ok(line)
+}
+
+/// Backslash-escape the `special` characters of `text`.
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn renders_line_protocol() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        data.insert("lost".to_string(), vec![]);
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            influx_measurement: "site health".to_string(),
+            influx_tags: vec![
+                "site=www.example.com".parse().unwrap(),
+                "env=staging, eu=1".parse().unwrap(),
+            ],
+            timestamp: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render(&page_graph, &options).unwrap(),
+            "site\\ health,env=staging\\,\\ eu\\=1,site=www.example.com \
+             node_count=3i,edge_count=1i,orphan_count=1i,dead_end_count=2i,\
+             component_count=2i,max_depth=1i 1700000000000000000\n"
+        );
+
+        let options = RenderOptions {
+            root: "missing".to_string(),
+            ..options
+        };
+        assert!(!render(&page_graph, &options).unwrap().contains("max_depth"));
+        assert!("env=".parse::<Tag>().is_err());
+        assert!("a=b\nc".parse::<Tag>().is_err());
+    }
+}
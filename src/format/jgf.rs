@@ -0,0 +1,133 @@
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+
+use crate::PageGraph;
+
+/// Top level of a JSON Graph Format document, holding a single graph.
+#[derive(Debug, Serialize)]
+struct JgfDocument<'a> {
+    graph: JgfGraph<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct JgfGraph<'a> {
+    directed: bool,
+    /// Nodes keyed by their id, the page name.
+    nodes: BTreeMap<&'a str, JgfNode<'a>>,
+    edges: Vec<JgfEdge<'a>>,
+}
+
+/// A node, labelled with the page title if there is one.
+#[derive(Debug, Serialize)]
+struct JgfNode<'a> {
+    label: &'a str,
+}
+
+/// An edge, `relation` is `links` or `contains`.
+#[derive(Debug, Serialize)]
+struct JgfEdge<'a> {
+    source: &'a str,
+    target: &'a str,
+    relation: &'a str,
+    metadata: JgfEdgeMetadata,
+}
+
+/// What the spec has no field for.
+#[derive(Debug, Serialize)]
+struct JgfEdgeMetadata {
+    /// Whether the target is a page of the site rather than another host.
+    internal: bool,
+}
+
+/// Render `page_graph` in the JSON Graph Format (version 2, <https://jsongraphformat.info>).
+pub fn render(page_graph: &PageGraph) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+
+    let nodes = graph
+        .nodes()
+        .map(|name| {
+            let label = page_graph.title(name).unwrap_or(name);
+            (name, JgfNode { label })
+        })
+        .collect();
+
+    let mut edges: Vec<_> = graph
+        .all_edges()
+        .map(|(source, target, &relation)| JgfEdge {
+            source,
+            target,
+            relation,
+            metadata: JgfEdgeMetadata {
+                internal: !page_graph.is_external(target),
+            },
+        })
+        .collect();
+    edges.sort_unstable_by_key(|edge| (edge.source, edge.target));
+
+    let document = JgfDocument {
+        graph: JgfGraph {
+            directed: true,
+            nodes,
+            edges,
+        },
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_graph_from_pages, Config};
+
+    #[test]
+    fn renders_valid_jgf() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<title>Home</title><a href="/a"> <a href="/b">"#.to_string(),
+            ),
+            ("a".to_string(), r#"<a href="/b">"#.to_string()),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+
+        let json: serde_json::Value = serde_json::from_str(&render(&page_graph).unwrap()).unwrap();
+
+        // The spec requires an object `graph` with object `nodes` and array `edges`,
+        // every edge having a `source` and `target` naming a node.
+        let graph = json["graph"].as_object().unwrap();
+        let nodes = graph["nodes"].as_object().unwrap();
+        let edges = graph["edges"].as_array().unwrap();
+        assert_eq!(graph["directed"], true);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges.len(), 3);
+        for edge in edges {
+            assert!(nodes.contains_key(edge["source"].as_str().unwrap()));
+            assert!(nodes.contains_key(edge["target"].as_str().unwrap()));
+            assert_eq!(edge["relation"], "links");
+            assert_eq!(edge["metadata"]["internal"], true);
+        }
+        assert_eq!(nodes["index"]["label"], "Home");
+        assert_eq!(nodes["b"]["label"], "b");
+    }
+
+    #[test]
+    fn marks_external_edges() {
+        let pages = vec![(
+            "index".to_string(),
+            r#"<a href="/a"> <a href="https://example.com/">"#.to_string(),
+        )];
+        let config = Config {
+            include_external: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config).with_external_nodes();
+
+        let json: serde_json::Value = serde_json::from_str(&render(&page_graph).unwrap()).unwrap();
+        let edges = json["graph"]["edges"].as_array().unwrap();
+        assert_eq!(edges[0]["target"], "a");
+        assert_eq!(edges[0]["metadata"]["internal"], true);
+        assert_eq!(edges[1]["target"], "example.com");
+        assert_eq!(edges[1]["metadata"]["internal"], false);
+    }
+}
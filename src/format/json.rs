@@ -0,0 +1,251 @@
+use anyhow::Context;
+
+use serde::{Deserialize, Serialize};
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::PageGraph;
+
+/// The page graph as plain JSON, with everything known about each page.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct JsonGraph<'a> {
+    #[serde(borrow)]
+    nodes: Vec<JsonNode<'a>>,
+    #[serde(borrow)]
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonNode<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    title: Option<Cow<'a, str>>,
+    /// Last modification of the source file in RFC 3339, e.g. `2021-03-01T09:30:00Z`.
+    modified: Option<String>,
+}
+
+/// A link, `relation` is `links` or `contains`.
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonEdge<'a> {
+    #[serde(borrow)]
+    source: Cow<'a, str>,
+    #[serde(borrow)]
+    target: Cow<'a, str>,
+    #[serde(borrow)]
+    relation: Cow<'a, str>,
+    /// How often the source links to the target.
+    #[serde(default = "one")]
+    weight: usize,
+    /// Whether the target is a page of the site rather than another host.
+    #[serde(default = "yes")]
+    internal: bool,
+}
+
+fn one() -> usize {
+    1
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// Render `page_graph` as JSON, nodes and edges sorted by name.
+pub fn render(page_graph: &PageGraph) -> Result<String, anyhow::Error> {
+    Ok(serde_json::to_string_pretty(&document(page_graph))?)
+}
+
+/// The nodes and edges `render` writes, for embedding them in other JSON.
+pub(super) fn document(page_graph: &PageGraph) -> JsonGraph<'_> {
+    let graph = page_graph.graph();
+
+    let mut nodes: Vec<_> = graph
+        .nodes()
+        .map(|name| JsonNode {
+            name: name.into(),
+            title: page_graph.title(name).map(Cow::from),
+            modified: page_graph
+                .modified(name)
+                .map(|time| humantime::format_rfc3339_seconds(time).to_string()),
+        })
+        .collect();
+    nodes.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    let weights = page_graph.link_counts();
+    let mut edges: Vec<_> = graph
+        .all_edges()
+        .map(|(source, target, &relation)| JsonEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: relation.into(),
+            weight: weights.get(&(source, target)).copied().unwrap_or(1),
+            internal: !page_graph.is_external(target),
+        })
+        .collect();
+    edges.sort_unstable_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+    JsonGraph { nodes, edges }
+}
+
+/// Read a page graph written by `render`. Links come back as often as their weight says,
+/// `contains` edges are left to `PageGraph::graph`, and links to other hosts come back as
+/// `PageGraph::external_links`. Only these, titles and modification times are saved; the other
+/// findings about pages, like placeholder links, meta robots or link texts, are not.
+pub fn parse(json: &str) -> Result<PageGraph, anyhow::Error> {
+    let parsed: JsonGraph = serde_json::from_str(json)?;
+
+    let mut links: HashMap<String, Vec<String>> = HashMap::new();
+    let mut external: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let mut linked = HashSet::new();
+    for edge in &parsed.edges {
+        linked.insert(edge.target.as_ref());
+        if edge.relation != "links" {
+            continue;
+        }
+        if edge.internal {
+            let targets = links.entry(edge.source.to_string()).or_default();
+            targets.extend((0..edge.weight).map(|_| edge.target.to_string()));
+        } else {
+            let hosts = external.entry(edge.source.to_string()).or_default();
+            hosts.insert(edge.target.to_string());
+        }
+    }
+    let mut titles = HashMap::new();
+    let mut modified = HashMap::new();
+    for node in &parsed.nodes {
+        if !linked.contains(node.name.as_ref()) {
+            links.entry(node.name.to_string()).or_default();
+        }
+        if let Some(title) = &node.title {
+            titles.insert(node.name.to_string(), title.to_string());
+        }
+        if let Some(time) = &node.modified {
+            let time = humantime::parse_rfc3339(time)
+                .context(format!("Invalid modification time of {}", node.name))?;
+            modified.insert(node.name.to_string(), time);
+        }
+    }
+    Ok(PageGraph::from(links)
+        .with_titles(titles)
+        .with_modified(modified)
+        .with_external_links(external))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn renders_modified_times() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        let mut modified = HashMap::new();
+        modified.insert(
+            "index".to_string(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+        );
+        let page_graph = PageGraph::from(data).with_modified(modified);
+
+        let json: serde_json::Value = serde_json::from_str(&render(&page_graph).unwrap()).unwrap();
+
+        let nodes = json["nodes"].as_array().unwrap();
+        assert_eq!(nodes[0]["name"], "a");
+        assert_eq!(nodes[0]["modified"], serde_json::Value::Null);
+        assert_eq!(nodes[1]["name"], "index");
+        assert_eq!(nodes[1]["modified"], "2020-09-13T12:26:40Z");
+        assert_eq!(json["edges"][0]["target"], "a");
+    }
+
+    #[test]
+    fn classifies_edges() {
+        let pages = vec![(
+            "index".to_string(),
+            r#"<a href="/a"> <a href="https://example.com/">"#.to_string(),
+        )];
+        let config = crate::Config {
+            include_external: true,
+            ..crate::Config::default()
+        };
+        let page_graph = crate::build_graph_from_pages(pages, &config).with_external_nodes();
+
+        let json: serde_json::Value = serde_json::from_str(&render(&page_graph).unwrap()).unwrap();
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(edges[0]["target"], "a");
+        assert_eq!(edges[0]["internal"], true);
+        assert_eq!(edges[1]["target"], "example.com");
+        assert_eq!(edges[1]["internal"], false);
+
+        let saved = parse(&render(&page_graph).unwrap()).unwrap();
+        assert_eq!(saved.graph().node_count(), 2);
+        assert_eq!(saved.external_links()["index"].len(), 1);
+        assert_eq!(
+            render(&saved.with_external_nodes()).unwrap(),
+            render(&page_graph).unwrap()
+        );
+    }
+
+    #[test]
+    fn saves_links_titles_and_times_only() {
+        let pages = vec![(
+            "index".to_string(),
+            r##"<title>Home</title> <a href="/a"> <a href="#"> <a href="/index">"##.to_string(),
+        )];
+        let page_graph = crate::build_graph_from_pages(pages, &crate::Config::default());
+        assert!(!page_graph.placeholder_links().is_empty());
+        assert!(!page_graph.dropped_self_links().is_empty());
+
+        let saved = parse(&render(&page_graph).unwrap()).unwrap();
+        assert_eq!(saved.links(), page_graph.links());
+        assert_eq!(saved.title("index"), Some("Home"));
+        assert!(saved.placeholder_links().is_empty());
+        assert!(saved.dropped_self_links().is_empty());
+    }
+
+    #[test]
+    fn merges_saved_graphs() {
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let mut first = HashMap::new();
+        first.insert("index".to_string(), page(&["blog", "blog", "shop"]));
+        first.insert("blog".to_string(), page(&["index"]));
+        let mut titles = HashMap::new();
+        titles.insert("blog".to_string(), "Old blog".to_string());
+        let first = PageGraph::from(first).with_titles(titles);
+        let saved = parse(&render(&first).unwrap()).unwrap();
+        assert_eq!(saved, first);
+
+        let mut second = HashMap::new();
+        second.insert("index".to_string(), page(&["blog", "team"]));
+        second.insert("blog".to_string(), page(&[]));
+        let mut titles = HashMap::new();
+        titles.insert("blog".to_string(), "Blog".to_string());
+        let second = PageGraph::from(second).with_titles(titles);
+
+        let merged = second.merged_with(saved);
+        let json: serde_json::Value = serde_json::from_str(&render(&merged).unwrap()).unwrap();
+        let edges: Vec<_> = json["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["source"].as_str().unwrap(),
+                    e["target"].as_str().unwrap(),
+                    e["weight"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("blog", "index", 1),
+                ("index", "blog", 3),
+                ("index", "shop", 1),
+                ("index", "team", 1)
+            ]
+        );
+        assert_eq!(merged.title("blog"), Some("Blog"));
+    }
+}
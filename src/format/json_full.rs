@@ -0,0 +1,142 @@
+use petgraph::Direction;
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+
+use super::json::{self, JsonGraph};
+use super::RenderOptions;
+use crate::analysis::find_components;
+use crate::stats::GraphStats;
+use crate::{depths, find_unreachable, PageGraph, Reachability};
+
+/// The `json` output with the analyses of the graph added under `analyses`.
+#[derive(Debug, Serialize)]
+struct FullGraph<'a> {
+    #[serde(flatten)]
+    graph: JsonGraph<'a>,
+    analyses: Analyses<'a>,
+}
+
+/// Results measured from `RenderOptions::root`. Like `GraphStats`, the field names are part
+/// of the output format, so add new fields rather than renaming existing ones.
+#[derive(Debug, Serialize)]
+struct Analyses<'a> {
+    /// The numbers of `--stats-json`.
+    stats: GraphStats,
+    /// Pages not reachable from the root, sorted.
+    orphans: Vec<&'a str>,
+    /// Pages without outgoing links, sorted.
+    dead_ends: Vec<&'a str>,
+    /// Maps pages to their inbound and outbound links.
+    degrees: BTreeMap<&'a str, Degree>,
+    /// Maps pages reachable from the root to their distance from it in clicks.
+    depths: BTreeMap<&'a str, usize>,
+    /// The pages of each weakly connected component, largest first.
+    components: Vec<Vec<&'a str>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Degree {
+    #[serde(rename = "in")]
+    inbound: usize,
+    #[serde(rename = "out")]
+    outbound: usize,
+}
+
+/// Render `page_graph` like `json`, with an `analyses` object of stats, orphans, dead ends,
+/// degrees, depths and components. `options.allowed_orphans` are left out of the orphans.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let root = options.root.as_str();
+    let roots = options.all_roots();
+
+    let mut orphans: Vec<_> = find_unreachable(&graph, &roots, Reachability::Forward)
+        .into_iter()
+        .filter(|&page| !options.allowed_orphans.contains(page))
+        .collect();
+    orphans.sort_unstable();
+    let degrees: BTreeMap<_, _> = graph
+        .nodes()
+        .map(|page| {
+            let degree = Degree {
+                inbound: graph.neighbors_directed(page, Direction::Incoming).count(),
+                outbound: graph.neighbors_directed(page, Direction::Outgoing).count(),
+            };
+            (page, degree)
+        })
+        .collect();
+    let dead_ends = degrees
+        .iter()
+        .filter(|(_, degree)| degree.outbound == 0)
+        .map(|(&page, _)| page)
+        .collect();
+
+    let analyses = Analyses {
+        stats: GraphStats::new(&graph, &roots),
+        orphans,
+        dead_ends,
+        degrees,
+        depths: depths(&graph, root).into_iter().collect(),
+        components: find_components(&graph),
+    };
+    let full = FullGraph {
+        graph: json::document(page_graph),
+        analyses,
+    };
+    Ok(serde_json::to_string_pretty(&full)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn embeds_analyses() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["index".to_string()]);
+        data.insert("lost".to_string(), vec!["found".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let json = render(&page_graph, &RenderOptions::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 5);
+        assert_eq!(json["edges"].as_array().unwrap().len(), 4);
+        let analyses = &json["analyses"];
+        let mut sections: Vec<_> = analyses.as_object().unwrap().keys().cloned().collect();
+        sections.sort();
+        assert_eq!(
+            sections,
+            vec![
+                "components",
+                "dead_ends",
+                "degrees",
+                "depths",
+                "orphans",
+                "stats"
+            ]
+        );
+        assert_eq!(analyses["stats"]["orphan_count"], 2);
+        assert_eq!(analyses["orphans"], serde_json::json!(["found", "lost"]));
+        assert_eq!(analyses["dead_ends"], serde_json::json!(["b", "found"]));
+        assert_eq!(analyses["degrees"]["index"]["in"], 1);
+        assert_eq!(analyses["degrees"]["index"]["out"], 2);
+        assert_eq!(analyses["depths"]["b"], 1);
+        assert!(analyses["depths"].get("lost").is_none());
+        assert_eq!(
+            analyses["components"],
+            serde_json::json!([["a", "b", "index"], ["found", "lost"]])
+        );
+
+        let options = RenderOptions {
+            allowed_orphans: vec!["lost".to_string()].into_iter().collect(),
+            ..RenderOptions::default()
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&page_graph, &options).unwrap()).unwrap();
+        assert_eq!(json["analyses"]["orphans"], serde_json::json!(["found"]));
+    }
+}
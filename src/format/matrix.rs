@@ -0,0 +1,123 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use super::RenderOptions;
+
+/// Graphs with more pages are refused unless forced, their matrix wouldn't fit a screen.
+pub const MAX_NODES: usize = 40;
+
+/// Page names are cut to this many characters.
+const MAX_LABEL: usize = 16;
+
+/// Render the adjacency matrix of `graph`: row `a`, column `b` is `1` if `a` links to `b`, else `.`.
+/// Column headers are the page names written top to bottom.
+pub fn render(
+    graph: &GraphMap<&str, &str, Directed>,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    if graph.node_count() > MAX_NODES && !options.force {
+        anyhow::bail!(
+            "{} pages are too many for a matrix (at most {}), use --force to render it anyway",
+            graph.node_count(),
+            MAX_NODES
+        );
+    }
+
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+    let labels: Vec<Vec<char>> = nodes.iter().map(|node| label(node)).collect();
+    let width = labels.iter().map(Vec::len).max().unwrap_or_default();
+
+    let mut matrix = String::new();
+    // Column headers, aligned to end right above the first row.
+    for line in 0..width {
+        matrix.push_str(&" ".repeat(width + 1));
+        let cells: Vec<_> = labels
+            .iter()
+            .map(|label| {
+                let offset = width - label.len();
+                if line < offset {
+                    ' '
+                } else {
+                    label[line - offset]
+                }
+            })
+            .map(String::from)
+            .collect();
+        matrix.push_str(cells.join(" ").trim_end());
+        matrix.push('\n');
+    }
+
+    for (from, label) in nodes.iter().zip(&labels) {
+        let label: String = label.iter().collect();
+        let cells: Vec<_> = nodes
+            .iter()
+            .map(|to| {
+                if graph.contains_edge(from, to) {
+                    "1"
+                } else {
+                    "."
+                }
+            })
+            .collect();
+        matrix.push_str(&format!(
+            "{:width$} {}\n",
+            label,
+            cells.join(" "),
+            width = width
+        ));
+    }
+    Ok(matrix)
+}
+
+/// `name` cut to `MAX_LABEL` characters, ending in `~` if cut.
+fn label(name: &str) -> Vec<char> {
+    let mut label: Vec<_> = name.chars().collect();
+    if label.len() > MAX_LABEL {
+        label.truncate(MAX_LABEL - 1);
+        label.push('~');
+    }
+    label
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_matrix() {
+        let mut data = HashMap::new();
+        data.insert("a".to_string(), vec!["bb".to_string()]);
+        data.insert("bb".to_string(), vec!["ccc".to_string()]);
+        data.insert("ccc".to_string(), vec!["a".to_string(), "ccc".to_string()]);
+        let graph = make_page_graph(&data);
+
+        assert_eq!(
+            render(&graph, &RenderOptions::default()).unwrap(),
+            "        c\n\
+             \x20     b c\n\
+             \x20   a b c\n\
+             a   . 1 .\n\
+             bb  . . 1\n\
+             ccc 1 . 1\n"
+        );
+    }
+
+    #[test]
+    fn refuses_large_graphs() {
+        let mut data = HashMap::new();
+        let pages: Vec<_> = (0..=MAX_NODES).map(|i| format!("page-{}", i)).collect();
+        data.insert("index".to_string(), pages);
+        let graph = make_page_graph(&data);
+
+        assert!(render(&graph, &RenderOptions::default()).is_err());
+        let options = RenderOptions {
+            force: true,
+            ..RenderOptions::default()
+        };
+        let matrix = render(&graph, &options).unwrap();
+        assert_eq!(matrix.lines().count(), MAX_NODES + 2 + "page-40".len());
+    }
+}
@@ -0,0 +1,75 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use std::fmt::Write;
+
+use super::RenderOptions;
+
+/// Render a GitHub-flavored Markdown table with a `| Source | Target |` row per link, sorted,
+/// for wiki pages. With `options.isolated`, pages without any links follow with an empty target.
+pub fn render(
+    graph: &GraphMap<&str, &str, Directed>,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    let mut edges: Vec<_> = graph.all_edges().map(|(a, b, _)| (a, b)).collect();
+    edges.sort_unstable();
+
+    let mut md = String::from("| Source | Target |\n| --- | --- |\n");
+    for (source, target) in edges {
+        writeln!(md, "| {} | {} |", escape(source), escape(target))?;
+    }
+    if options.isolated {
+        let mut isolated: Vec<_> = graph
+            .nodes()
+            .filter(|&node| {
+                graph.neighbors(node).next().is_none()
+                    && graph
+                        .neighbors_directed(node, Direction::Incoming)
+                        .next()
+                        .is_none()
+            })
+            .collect();
+        isolated.sort_unstable();
+        for node in isolated {
+            writeln!(md, "| {} | |", escape(node))?;
+        }
+    }
+    Ok(md)
+}
+
+/// Escape `name` for a table cell, where `|` would end the cell.
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_table() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a|b".to_string()]);
+        data.insert("lonely".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+        let options = RenderOptions {
+            isolated: true,
+            ..RenderOptions::default()
+        };
+
+        let md = render(&graph, &options).unwrap();
+
+        let lines: Vec<_> = md.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "| Source | Target |",
+                "| --- | --- |",
+                r"| index | a\|b |",
+                "| lonely | |"
+            ]
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashSet;
+use std::io;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::traffic::Traffic;
+use crate::PageGraph;
+
+pub mod ascii;
+pub mod canvas;
+pub mod cypher;
+pub mod dgml;
+pub mod dot;
+pub mod edges;
+pub mod gvjson;
+pub mod influx;
+pub mod jgf;
+pub mod json;
+pub mod json_full;
+pub mod matrix;
+pub mod md_table;
+pub mod nodes_csv;
+pub mod org;
+pub mod outline;
+pub mod pdf;
+pub mod summary_md;
+pub mod svg;
+pub mod tgf;
+pub mod treemap;
+pub mod turtle;
+
+/// Output formats selectable with `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Graphviz DOT.
+    Dot,
+    /// Graphviz's native JSON, as produced by `dot -Tjson0`.
+    GvJson,
+    /// One CSV row per page with degrees, depth and title.
+    NodesCsv,
+    /// An SVG drawing, laid out without Graphviz.
+    Svg,
+    /// RDF triples in Turtle syntax.
+    Turtle,
+    /// An adjacency matrix as text, for small graphs.
+    Matrix,
+    /// Cypher statements for importing into Neo4j.
+    Cypher,
+    /// The JSON Graph Format, see jsongraphformat.info.
+    Jgf,
+    /// Nodes with their metadata and edges as JSON.
+    Json,
+    /// Nested HTML lists of the pages below the root, expandable with `<details>`.
+    Outline,
+    /// One `source target` line per link.
+    Edges,
+    /// SVG treemap of inbound links, grouped by section.
+    Treemap,
+    /// Org mode outline of the link tree from the root.
+    Org,
+    /// Markdown report with stats and orphans, for pull request comments.
+    SummaryMd,
+    /// Single page PDF drawing, without needing Graphviz.
+    Pdf,
+    /// InfluxDB line protocol with the graph stats, for trending them.
+    Influx,
+    /// Boxes and connectors as text, for small graphs.
+    Ascii,
+    /// Trivial Graph Format, for importing into yEd.
+    Tgf,
+    /// Obsidian canvas with a card per page.
+    Canvas,
+    /// GitHub-flavored Markdown table of the links, for wiki pages.
+    MdTable,
+    /// The `json` output with orphans, dead ends, degrees, depths and components.
+    JsonFull,
+    /// DGML for the graph viewer of Visual Studio.
+    Dgml,
+}
+
+impl Format {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &[
+        "dot",
+        "gvjson",
+        "nodes-csv",
+        "svg",
+        "turtle",
+        "matrix",
+        "cypher",
+        "jgf",
+        "json",
+        "outline",
+        "edges",
+        "treemap",
+        "org",
+        "summary-md",
+        "pdf",
+        "influx",
+        "ascii",
+        "tgf",
+        "canvas",
+        "md-table",
+        "json-full",
+        "dgml",
+    ];
+
+    /// The usual file extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Dot => "dot",
+            Format::GvJson => "json",
+            Format::NodesCsv => "csv",
+            Format::Svg => "svg",
+            Format::Turtle => "ttl",
+            Format::Matrix => "txt",
+            Format::Cypher => "cypher",
+            Format::Jgf => "json",
+            Format::Json => "json",
+            Format::Outline => "html",
+            Format::Edges => "txt",
+            Format::Treemap => "svg",
+            Format::Org => "org",
+            Format::SummaryMd => "md",
+            Format::Pdf => "pdf",
+            Format::Influx => "lp",
+            Format::Ascii => "txt",
+            Format::Tgf => "tgf",
+            Format::Canvas => "canvas",
+            Format::MdTable => "md",
+            Format::JsonFull => "json",
+            Format::Dgml => "dgml",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "gvjson" => Ok(Format::GvJson),
+            "nodes-csv" => Ok(Format::NodesCsv),
+            "svg" => Ok(Format::Svg),
+            "turtle" => Ok(Format::Turtle),
+            "matrix" => Ok(Format::Matrix),
+            "cypher" => Ok(Format::Cypher),
+            "jgf" => Ok(Format::Jgf),
+            "json" => Ok(Format::Json),
+            "outline" => Ok(Format::Outline),
+            "edges" => Ok(Format::Edges),
+            "treemap" => Ok(Format::Treemap),
+            "org" => Ok(Format::Org),
+            "summary-md" => Ok(Format::SummaryMd),
+            "pdf" => Ok(Format::Pdf),
+            "influx" => Ok(Format::Influx),
+            "ascii" => Ok(Format::Ascii),
+            "tgf" => Ok(Format::Tgf),
+            "canvas" => Ok(Format::Canvas),
+            "md-table" => Ok(Format::MdTable),
+            "json-full" => Ok(Format::JsonFull),
+            "dgml" => Ok(Format::Dgml),
+            other => Err(format!(
+                "unknown format {}, expected one of {}",
+                other,
+                Format::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Predicate for links in RDF output, unless configured otherwise.
+pub const DEFAULT_RDF_PREDICATE: &str = "https://schema.org/relatedLink";
+
+/// Settings shared by the output formats.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// The page reachability and depth are measured from.
+    pub root: String,
+    /// Further pages reachability is measured from, like those of `--roots-from`.
+    pub roots: Vec<String>,
+    /// Pages left out of the lists of orphans, like those matching `--orphan-allow-regex`.
+    pub allowed_orphans: HashSet<String>,
+    /// Prefix turning page names into absolute URLs, ending in a slash.
+    pub base_url: String,
+    /// IRI of the predicate for links in RDF output.
+    pub rdf_predicate: String,
+    /// Navigation counts, added to DOT edges as a `traffic` attribute.
+    pub traffic: Traffic,
+    /// Render even graphs too large for the format to be readable.
+    pub force: bool,
+    /// Pages last modified before this are highlighted in DOT output.
+    pub stale_before: Option<SystemTime>,
+    /// List pages without any links in `edges` output.
+    pub isolated: bool,
+    /// Graph attributes like `rankdir` added to the DOT header.
+    pub dot_attributes: Vec<dot::Attribute>,
+    /// Label of the links in DOT output, they have none if `None`.
+    pub edge_label: Option<String>,
+    /// Make DOT edges thicker by this weight.
+    pub weight_source: Option<dot::WeightSource>,
+    /// Fill DOT nodes by their depth from `root` with these colors.
+    pub depth_palette: Option<dot::Palette>,
+    /// Add tooltips with degrees, depth and title to DOT nodes.
+    pub tooltips: bool,
+    /// Measurement name of `influx` output.
+    pub influx_measurement: String,
+    /// Tags added to `influx` output.
+    pub influx_tags: Vec<influx::Tag>,
+    /// When the output was made, for formats recording it. Now if `None`.
+    pub timestamp: Option<SystemTime>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            root: "index".to_string(),
+            roots: Vec::new(),
+            allowed_orphans: HashSet::new(),
+            base_url: "https://www.traplinked.com/".to_string(),
+            rdf_predicate: DEFAULT_RDF_PREDICATE.to_string(),
+            traffic: Traffic::new(),
+            force: false,
+            stale_before: None,
+            isolated: false,
+            dot_attributes: Vec::new(),
+            edge_label: None,
+            weight_source: None,
+            depth_palette: None,
+            tooltips: false,
+            influx_measurement: influx::DEFAULT_MEASUREMENT.to_string(),
+            influx_tags: Vec::new(),
+            timestamp: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// `root` and the other `roots`, for finding orphans.
+    pub fn all_roots(&self) -> Vec<&str> {
+        std::iter::once(self.root.as_str())
+            .chain(self.roots.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// Writes page graphs in one output format. There is a writer for each `Format`, see
+/// `Format::writer`; library users can implement it for formats of their own.
+pub trait OutputWriter {
+    /// Write `page_graph` to `sink`.
+    fn write(&self, page_graph: &PageGraph, sink: &mut dyn io::Write) -> Result<(), anyhow::Error>;
+}
+
+/// Declare a writer type for each format, rendering with the given expression, and
+/// `Format::writer` choosing between them.
+macro_rules! writers {
+    ($($format:ident => $writer:ident, |$page_graph:ident, $options:pat| $render:expr;)*) => {
+        $(
+            #[doc = concat!("Writes `Format::", stringify!($format), "` output.")]
+            #[derive(Debug, Clone, Copy)]
+            pub struct $writer<'a>(pub &'a RenderOptions);
+
+            impl OutputWriter for $writer<'_> {
+                fn write(
+                    &self,
+                    $page_graph: &PageGraph,
+                    sink: &mut dyn io::Write,
+                ) -> Result<(), anyhow::Error> {
+                    let $options = self.0;
+                    let output: String = $render?;
+                    sink.write_all(output.as_bytes())?;
+                    Ok(())
+                }
+            }
+        )*
+
+        impl Format {
+            /// The writer of the format, rendering with `options`.
+            pub fn writer(self, options: &RenderOptions) -> Box<dyn OutputWriter + '_> {
+                match self {
+                    $(Format::$format => Box::new($writer(options)),)*
+                }
+            }
+        }
+    };
+}
+
+writers! {
+    Dot => DotWriter, |page_graph, options| dot::render(page_graph, options);
+    GvJson => GvJsonWriter, |page_graph, _| gvjson::render(&page_graph.graph());
+    NodesCsv => NodesCsvWriter, |page_graph, options| nodes_csv::render(page_graph, options);
+    Svg => SvgWriter, |page_graph, options| svg::render(page_graph, options);
+    Turtle => TurtleWriter, |page_graph, options| turtle::render(page_graph, options);
+    Matrix => MatrixWriter, |page_graph, options| matrix::render(&page_graph.graph(), options);
+    Cypher => CypherWriter, |page_graph, _| cypher::render(&page_graph.graph());
+    Jgf => JgfWriter, |page_graph, _| jgf::render(page_graph);
+    Json => JsonWriter, |page_graph, _| json::render(page_graph);
+    Outline => OutlineWriter, |page_graph, options| outline::render(page_graph, options);
+    Edges => EdgesWriter, |page_graph, options| edges::render(&page_graph.graph(), options);
+    Treemap => TreemapWriter, |page_graph, _| treemap::render(page_graph);
+    Org => OrgWriter, |page_graph, options| org::render(page_graph, options);
+    SummaryMd => SummaryMdWriter, |page_graph, options| summary_md::render(page_graph, options);
+    Pdf => PdfWriter, |page_graph, options| pdf::render(page_graph, options);
+    Influx => InfluxWriter, |page_graph, options| influx::render(page_graph, options);
+    Ascii => AsciiWriter, |page_graph, options| ascii::render(&page_graph.graph(), options);
+    Tgf => TgfWriter, |page_graph, _| tgf::render(&page_graph.graph());
+    Canvas => CanvasWriter, |page_graph, options| canvas::render(&page_graph.graph(), options);
+    MdTable => MdTableWriter, |page_graph, options| md_table::render(&page_graph.graph(), options);
+    JsonFull => JsonFullWriter, |page_graph, options| json_full::render(page_graph, options);
+    Dgml => DgmlWriter, |page_graph, _| dgml::render(page_graph);
+}
+
+/// Render `page_graph` in the given `format`.
+pub fn render(
+    page_graph: &PageGraph,
+    format: Format,
+    options: &RenderOptions,
+) -> Result<String, anyhow::Error> {
+    let mut output = Vec::new();
+    format.writer(options).write(page_graph, &mut output)?;
+    Ok(String::from_utf8(output)?)
+}
+
+/// Escape `text` for use in XML text and attribute values.
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// The number of pages, for a writer defined outside this module.
+    struct PageCount;
+
+    impl OutputWriter for PageCount {
+        fn write(
+            &self,
+            page_graph: &PageGraph,
+            sink: &mut dyn io::Write,
+        ) -> Result<(), anyhow::Error> {
+            writeln!(sink, "{}", page_graph.graph().node_count())?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_through_trait_objects() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        let page_graph = PageGraph::from(data);
+        let options = RenderOptions::default();
+
+        let writers: Vec<Box<dyn OutputWriter>> = vec![
+            Format::Dot.writer(&options),
+            Box::new(EdgesWriter(&options)),
+            Box::new(PageCount),
+        ];
+        let outputs: Vec<_> = writers
+            .iter()
+            .map(|writer| {
+                let mut output = Vec::new();
+                writer.write(&page_graph, &mut output).unwrap();
+                String::from_utf8(output).unwrap()
+            })
+            .collect();
+
+        assert_eq!(outputs[0], dot::render(&page_graph, &options).unwrap());
+        assert!(outputs[0].starts_with("digraph {"));
+        assert_eq!(outputs[1], "index a\n");
+        assert_eq!(outputs[2], "2\n");
+        assert_eq!(
+            render(&page_graph, Format::Edges, &options).unwrap(),
+            outputs[1]
+        );
+    }
+}
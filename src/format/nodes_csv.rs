@@ -0,0 +1,71 @@
+use petgraph::Direction;
+
+use std::fmt::Write;
+
+use super::RenderOptions;
+use crate::{depths, find_unreachable, PageGraph, Reachability};
+
+/// Render one row per page with `name,indegree,outdegree,depth,is_orphan,is_dead_end,title`.
+/// `depth` is empty for pages not reachable from the root.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let depths = depths(&graph, &options.root);
+    let orphans = find_unreachable(&graph, &options.all_roots(), Reachability::Forward);
+
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+
+    let mut csv = String::from("name,indegree,outdegree,depth,is_orphan,is_dead_end,title\n");
+    for node in nodes {
+        let indegree = graph.neighbors_directed(node, Direction::Incoming).count();
+        let outdegree = graph.neighbors_directed(node, Direction::Outgoing).count();
+        let depth = depths.get(node).map(usize::to_string).unwrap_or_default();
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            escape(node),
+            indegree,
+            outdegree,
+            depth,
+            orphans.contains(node),
+            outdegree == 0,
+            escape(page_graph.title(node).unwrap_or_default())
+        )?;
+    }
+    Ok(csv)
+}
+
+/// Quote `field` if it contains a comma, quote or line break.
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_graph_from_pages, Config};
+
+    #[test]
+    fn renders_node_rows() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<title>Home</title><a href="https://www.traplinked.com/a,b">"#.to_string(),
+            ),
+            ("lost".to_string(), "<title>Say \"hi\"</title>".to_string()),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+
+        assert_eq!(
+            render(&page_graph, &RenderOptions::default()).unwrap(),
+            "name,indegree,outdegree,depth,is_orphan,is_dead_end,title\n\
+             \"a,b\",1,0,1,false,true,\n\
+             index,0,1,0,false,false,Home\n\
+             lost,0,0,,true,true,\"Say \"\"hi\"\"\"\n"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::RenderOptions;
+use crate::PageGraph;
+
+/// Render the pages as an Org mode outline, each page a heading nested below the page it was
+/// first reached from, walking links depth-first from the root in alphabetical order.
+///
+/// Pages reached again, e.g. through a link back up, get a leaf heading marked `(seen)`.
+/// Pages the root can't reach follow under a top-level `Orphans` heading, walked the same way.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let mut visited = HashSet::new();
+    let mut org = String::new();
+
+    if graph.contains_node(&options.root) {
+        write_tree(
+            &mut org,
+            page_graph,
+            &graph,
+            options,
+            &mut visited,
+            &options.root,
+            1,
+        )?;
+    }
+
+    let mut orphans: Vec<_> = graph.nodes().filter(|n| !visited.contains(n)).collect();
+    if !orphans.is_empty() {
+        org.push_str("* Orphans\n");
+        orphans.sort_unstable();
+        for orphan in orphans {
+            if !visited.contains(orphan) {
+                write_tree(
+                    &mut org,
+                    page_graph,
+                    &graph,
+                    options,
+                    &mut visited,
+                    orphan,
+                    2,
+                )?;
+            }
+        }
+    }
+    Ok(org)
+}
+
+/// Write the headings of the pages `start` reaches and which aren't `visited` yet.
+fn write_tree<'a>(
+    org: &mut String,
+    page_graph: &PageGraph,
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    options: &RenderOptions,
+    visited: &mut HashSet<&'a str>,
+    start: &'a str,
+    level: usize,
+) -> Result<(), anyhow::Error> {
+    // Pages to write with their heading level, the next one on top.
+    let mut stack = vec![(start, level)];
+    while let Some((page, level)) = stack.pop() {
+        let title = page_graph.title(page).unwrap_or(page);
+        let link = format!(
+            "[[{}{}][{}]]",
+            options.base_url,
+            page,
+            title.replace('[', "{").replace(']', "}")
+        );
+        let stars = "*".repeat(level);
+        if !visited.insert(page) {
+            writeln!(org, "{} {} (seen)", stars, link)?;
+            continue;
+        }
+        writeln!(org, "{} {}", stars, link)?;
+
+        let mut children: Vec<_> = graph.neighbors(page).filter(|&n| n != page).collect();
+        children.sort_unstable_by(|a, b| b.cmp(a));
+        stack.extend(children.into_iter().map(|child| (child, level + 1)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn nests_headings() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["blog", "team"]));
+        data.insert("blog".to_string(), page(&["index", "post", "blog"]));
+        data.insert("post".to_string(), page(&["team"]));
+        data.insert("lost".to_string(), page(&["found"]));
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            base_url: "/".to_string(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render(&page_graph, &options).unwrap(),
+            "* [[/index][index]]
+** [[/blog][blog]]
+*** [[/index][index]] (seen)
+*** [[/post][post]]
+**** [[/team][team]]
+** [[/team][team]] (seen)
+* Orphans
+** [[/found][found]]
+** [[/lost][lost]]
+*** [[/found][found]] (seen)
+"
+        );
+    }
+}
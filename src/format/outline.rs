@@ -0,0 +1,110 @@
+use petgraph::Direction;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{escape_xml, RenderOptions};
+use crate::{depths, PageGraph};
+
+/// Render the pages reachable from the root as nested HTML lists, each page with children in a
+/// `<details>` element so readers can expand it.
+///
+/// Every page appears once, below the alphabetically first page linking to it from one level
+/// closer to the root. Links back up or across, which would make the tree cyclic, are left out,
+/// as are pages the root can't reach.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let depths = depths(&graph, &options.root);
+
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (&page, &depth) in &depths {
+        let parent = graph
+            .neighbors_directed(page, Direction::Incoming)
+            .filter(|parent| depths.get(parent).is_some_and(|&d| d + 1 == depth))
+            .min();
+        if let Some(parent) = parent {
+            children.entry(parent).or_default().push(page);
+        }
+    }
+    for pages in children.values_mut() {
+        pages.sort_unstable();
+    }
+
+    let mut html = String::from("<ul class=\"outline\">\n");
+    if depths.contains_key(options.root.as_str()) {
+        write_page(&mut html, page_graph, options, &children, &options.root, 1)?;
+    }
+    html.push_str("</ul>\n");
+    Ok(html)
+}
+
+/// Write the `<li>` of `page` and, recursively, its children.
+fn write_page(
+    html: &mut String,
+    page_graph: &PageGraph,
+    options: &RenderOptions,
+    children: &HashMap<&str, Vec<&str>>,
+    page: &str,
+    level: usize,
+) -> Result<(), anyhow::Error> {
+    let indent = "  ".repeat(level);
+    let link = format!(
+        r#"<a href="{}">{}</a>"#,
+        escape_xml(&format!("{}{}", options.base_url, page)),
+        escape_xml(page_graph.title(page).unwrap_or(page))
+    );
+
+    match children.get(page) {
+        None => writeln!(html, "{}<li>{}</li>", indent, link)?,
+        Some(pages) => {
+            writeln!(html, "{}<li><details open>", indent)?;
+            writeln!(html, "{}  <summary>{}</summary>", indent, link)?;
+            writeln!(html, "{}  <ul>", indent)?;
+            for child in pages {
+                write_page(html, page_graph, options, children, child, level + 2)?;
+            }
+            writeln!(html, "{}  </ul>", indent)?;
+            writeln!(html, "{}</details></li>", indent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nests_pages_by_depth() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["blog", "team"]));
+        data.insert("blog".to_string(), page(&["index", "post"]));
+        data.insert("team".to_string(), page(&["post"]));
+        data.insert("lost".to_string(), page(&["index"]));
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            base_url: "/".to_string(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render(&page_graph, &options).unwrap(),
+            r#"<ul class="outline">
+  <li><details open>
+    <summary><a href="/index">index</a></summary>
+    <ul>
+      <li><details open>
+        <summary><a href="/blog">blog</a></summary>
+        <ul>
+          <li><a href="/post">post</a></li>
+        </ul>
+      </details></li>
+      <li><a href="/team">team</a></li>
+    </ul>
+  </details></li>
+</ul>
+"#
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::Write;
+
+use super::RenderOptions;
+use crate::layout::{layered, CHAR_WIDTH, NODE_HEIGHT};
+use crate::PageGraph;
+
+/// Above this many pages, `main` warns that the drawing gets hard to read.
+pub const MAX_NODES: usize = 300;
+
+/// Largest page side PDF viewers have to support, in points. Larger drawings are scaled down.
+const MAX_SIDE: f64 = 14400.0;
+
+/// Courier glyphs are 0.6 em wide, this size makes them as wide as `CHAR_WIDTH`.
+const FONT_SIZE: f64 = CHAR_WIDTH / 0.6;
+
+/// Length of the arrow heads, in points.
+const ARROW: f64 = 6.0;
+
+/// Render `page_graph` as a single page PDF with the same layered drawing as `svg`.
+///
+/// The file is plain ASCII without compression and uses the standard Courier font, so nothing
+/// needs to be embedded. Labels outside of Windows-1252 are drawn as `?`.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let layout = layered(&graph, &options.root);
+    let scale = (MAX_SIDE / layout.width.max(layout.height)).min(1.0);
+    let (width, height) = (layout.width.max(1.0), layout.height.max(1.0));
+    // PDF coordinates start at the bottom left, the layout at the top left.
+    let flip = |y: f64| height - y;
+
+    let mut content = String::new();
+    writeln!(content, "{:.4} 0 0 {:.4} 0 0 cm", scale, scale)?;
+    content.push_str("0.53 G 0.53 g 0.8 w\n");
+    let mut edges: Vec<_> = graph.all_edges().map(|(a, b, _)| (a, b)).collect();
+    edges.sort_unstable();
+    for (source, target) in edges {
+        if source == target {
+            continue;
+        }
+        let (from, to) = (layout.nodes[source], layout.nodes[target]);
+        let (y1, y2) = if from.y <= to.y {
+            (from.y + NODE_HEIGHT / 2.0, to.y - NODE_HEIGHT / 2.0)
+        } else {
+            (from.y - NODE_HEIGHT / 2.0, to.y + NODE_HEIGHT / 2.0)
+        };
+        let (x1, y1, x2, y2) = (from.x, flip(y1), to.x, flip(y2));
+        writeln!(content, "{:.1} {:.1} m {:.1} {:.1} l S", x1, y1, x2, y2)?;
+
+        let length = (x2 - x1).hypot(y2 - y1).max(f64::EPSILON);
+        let (dx, dy) = ((x2 - x1) / length * ARROW, (y2 - y1) / length * ARROW);
+        writeln!(
+            content,
+            "{:.1} {:.1} m {:.1} {:.1} l {:.1} {:.1} l f",
+            x2,
+            y2,
+            x2 - dx - dy / 2.0,
+            y2 - dy + dx / 2.0,
+            x2 - dx + dy / 2.0,
+            y2 - dy - dx / 2.0
+        )?;
+    }
+
+    let mut nodes: Vec<_> = layout.nodes.iter().collect();
+    nodes.sort_unstable_by_key(|(name, _)| *name);
+    for (name, node) in nodes {
+        let x = node.x - node.width / 2.0;
+        writeln!(
+            content,
+            "0 G 1 g {:.1} {:.1} {:.1} {:.1} re B",
+            x,
+            flip(node.y + NODE_HEIGHT / 2.0),
+            node.width,
+            NODE_HEIGHT
+        )?;
+        let label_width = name.chars().count() as f64 * CHAR_WIDTH;
+        writeln!(
+            content,
+            "0 g BT /F1 {:.2} Tf {:.1} {:.1} Td ({}) Tj ET",
+            FONT_SIZE,
+            node.x - label_width / 2.0,
+            flip(node.y) - FONT_SIZE * 0.3,
+            escape(name)
+        )?;
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.1} {:.1}] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>",
+            width * scale,
+            height * scale
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj\n{}\nendobj", i + 1, object)?;
+    }
+    let xref = pdf.len();
+    writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1)?;
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset)?;
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )?;
+    Ok(pdf)
+}
+
+/// Quote `text` for a PDF string in Windows-1252, with octal escapes to keep the file ASCII.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        let code = match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+                continue;
+            }
+            ' '..='~' => {
+                escaped.push(c);
+                continue;
+            }
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '\u{a0}'..='\u{ff}' => c as u32,
+            _ => '?' as u32,
+        };
+        write!(escaped, "\\{:03o}", code).unwrap();
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_valid_pdf() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["über(uns)".to_string(), "c".to_string()],
+        );
+        data.insert("c".to_string(), vec!["index".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let pdf = render(&page_graph, &RenderOptions::default()).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.is_ascii());
+        assert!(pdf.contains(r"(\374ber\(uns\)) Tj"));
+
+        // The cross-reference table points at each object and startxref at the table.
+        let startxref: usize = pdf
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(pdf[startxref..].starts_with("xref\n0 6\n"));
+        let entries = pdf[startxref..].lines().skip(3).take(5);
+        for (i, entry) in entries.enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+
+        let length: usize = pdf
+            .split("/Length ")
+            .nth(1)
+            .unwrap()
+            .split(' ')
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let stream = pdf.find(">>\nstream\n").unwrap() + ">>\nstream\n".len();
+        assert!(pdf[stream + length..].starts_with("endstream\n"));
+    }
+}
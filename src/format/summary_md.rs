@@ -0,0 +1,84 @@
+use std::fmt::Write;
+
+use super::RenderOptions;
+use crate::stats::GraphStats;
+use crate::{find_unreachable, PageGraph, Reachability};
+
+/// Render a short Markdown report for pasting into a GitHub comment: a table of `GraphStats`
+/// and the orphans in a collapsed `<details>` section, without `options.allowed_orphans`.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let roots = options.all_roots();
+    let stats = GraphStats::new(&graph, &roots);
+    let max_depth = stats
+        .max_depth
+        .map_or("-".to_string(), |depth| depth.to_string());
+
+    let mut md = String::from("## Page graph\n\n| | |\n| --- | ---: |\n");
+    for (label, value) in &[
+        ("Pages", stats.node_count.to_string()),
+        ("Links", stats.edge_count.to_string()),
+        ("Orphans", stats.orphan_count.to_string()),
+        ("Dead ends", stats.dead_end_count.to_string()),
+        ("Components", stats.component_count.to_string()),
+        ("Max depth", max_depth),
+    ] {
+        writeln!(md, "| {} | {} |", label, value)?;
+    }
+
+    let mut orphans: Vec<_> = find_unreachable(&graph, &roots, Reachability::Forward)
+        .into_iter()
+        .filter(|&page| !options.allowed_orphans.contains(page))
+        .collect();
+    orphans.sort_unstable();
+    if !orphans.is_empty() {
+        writeln!(
+            md,
+            "\n<details>\n<summary>Orphans ({})</summary>\n",
+            orphans.len()
+        )?;
+        for orphan in orphans {
+            writeln!(md, "- `{}`", orphan.replace('`', "'"))?;
+        }
+        md.push_str("\n</details>\n");
+    }
+    Ok(md)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_table_and_sections() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string()]);
+        data.insert("lost".to_string(), vec!["a".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let md = render(&page_graph, &RenderOptions::default()).unwrap();
+
+        assert!(md.starts_with("## Page graph\n\n| | |\n| --- | ---: |\n"));
+        assert!(md.contains("| Pages | 3 |\n"));
+        assert!(md.contains("| Max depth | 1 |\n"));
+        assert!(
+            md.contains("<details>\n<summary>Orphans (1)</summary>\n\n- `lost`\n\n</details>\n")
+        );
+
+        let options = RenderOptions {
+            roots: vec!["lost".to_string()],
+            ..RenderOptions::default()
+        };
+        let md = render(&page_graph, &options).unwrap();
+        assert!(md.contains("| Orphans | 0 |\n"));
+        assert!(!md.contains("<details>"));
+
+        let options = RenderOptions {
+            allowed_orphans: vec!["lost".to_string()].into_iter().collect(),
+            ..RenderOptions::default()
+        };
+        let md = render(&page_graph, &options).unwrap();
+        assert!(!md.contains("<details>"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Write;
+
+use super::{escape_xml, RenderOptions};
+use crate::layout::{layered, NODE_HEIGHT};
+use crate::PageGraph;
+
+/// Render `page_graph` as an SVG drawing, laid out in layers by depth from the root.
+/// Meant for small to medium sites, without needing Graphviz.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let layout = layered(&graph, &options.root);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.0} {h:.0}" font-family="monospace" font-size="12">"#,
+        w = layout.width,
+        h = layout.height
+    )?;
+    svg.push_str(
+        r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z"/></marker></defs>"#,
+    );
+    svg.push('\n');
+
+    let mut edges: Vec<_> = graph.all_edges().map(|(a, b, _)| (a, b)).collect();
+    edges.sort_unstable();
+    for (source, target) in edges {
+        if source == target {
+            continue;
+        }
+        let (from, to) = (layout.nodes[source], layout.nodes[target]);
+        // Leave boxes at the bottom and enter them at the top, unless going up.
+        let (y1, y2) = if from.y <= to.y {
+            (from.y + NODE_HEIGHT / 2.0, to.y - NODE_HEIGHT / 2.0)
+        } else {
+            (from.y - NODE_HEIGHT / 2.0, to.y + NODE_HEIGHT / 2.0)
+        };
+        writeln!(
+            svg,
+            r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#888" marker-end="url(#arrow)"/>"##,
+            from.x, y1, to.x, y2
+        )?;
+    }
+
+    let mut nodes: Vec<_> = layout.nodes.iter().collect();
+    nodes.sort_unstable_by_key(|(name, _)| *name);
+    for (name, node) in nodes {
+        writeln!(
+            svg,
+            r#"<g><rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="4" fill="white" stroke="black"/><text x="{:.1}" y="{:.1}" text-anchor="middle" dominant-baseline="central">{}</text></g>"#,
+            node.x - node.width / 2.0,
+            node.y - NODE_HEIGHT / 2.0,
+            node.width,
+            NODE_HEIGHT,
+            node.x,
+            node.y,
+            escape_xml(name)
+        )?;
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_svg() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["a&b".to_string(), "c".to_string()],
+        );
+        data.insert("c".to_string(), vec!["index".to_string()]);
+        let page_graph = PageGraph::from(data);
+
+        let svg = render(&page_graph, &RenderOptions::default()).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 3);
+        assert_eq!(svg.matches("<line ").count(), 3);
+        assert!(svg.contains(">a&amp;b</text>"));
+    }
+}
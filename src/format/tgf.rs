@@ -0,0 +1,62 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Render `graph` in the Trivial Graph Format read by yEd: one `number name` line per page,
+/// sorted by name and numbered from 1, a `#` line, then one `source target` line of numbers per
+/// link. Edges from a page to its fragments are labelled `contains`.
+pub fn render(graph: &GraphMap<&str, &str, Directed>) -> Result<String, anyhow::Error> {
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+    let numbers: HashMap<&str, usize> = nodes.iter().zip(1..).map(|(&n, i)| (n, i)).collect();
+
+    let mut tgf = String::new();
+    for node in &nodes {
+        writeln!(tgf, "{} {}", numbers[node], node)?;
+    }
+    tgf.push_str("#\n");
+
+    let mut edges: Vec<_> = graph
+        .all_edges()
+        .map(|(source, target, &relation)| (numbers[source], numbers[target], relation))
+        .collect();
+    edges.sort_unstable();
+    for (source, target, relation) in edges {
+        match relation {
+            "contains" => writeln!(tgf, "{} {} contains", source, target)?,
+            _ => writeln!(tgf, "{} {}", source, target)?,
+        }
+    }
+    Ok(tgf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+
+    #[test]
+    fn numbers_nodes_for_edges() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["b".to_string(), "a".to_string()]);
+        data.insert("a".to_string(), vec!["b".to_string()]);
+        let graph = make_page_graph(&data);
+
+        let tgf = render(&graph).unwrap();
+        assert_eq!(tgf, "1 a\n2 b\n3 index\n#\n1 2\n3 1\n3 2\n");
+
+        // Every edge line references declared node numbers.
+        let (nodes, edges) = tgf.split_once("#\n").unwrap();
+        let declared: Vec<_> = nodes
+            .lines()
+            .map(|l| l.split(' ').next().unwrap())
+            .collect();
+        for edge in edges.lines() {
+            let mut numbers = edge.split(' ');
+            assert!(declared.contains(&numbers.next().unwrap()));
+            assert!(declared.contains(&numbers.next().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use petgraph::Direction;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::escape_xml;
+use crate::layout::CHAR_WIDTH;
+use crate::PageGraph;
+
+/// Size of the drawing, in pixels.
+const WIDTH: f64 = 960.0;
+const HEIGHT: f64 = 600.0;
+/// Height reserved for the name of a section above its pages, in pixels.
+const HEADER: f64 = 16.0;
+
+/// Render `page_graph` as an SVG treemap: one column per section, its width proportional to the
+/// inbound links of the pages in it, split into one rectangle per page sized by its in-degree.
+/// Sections are the first path segments, as in `group_by_section`; pages without inbound links
+/// take no space and are left out. Simple slice-and-dice, so tiny pages become thin slivers.
+pub fn render(page_graph: &PageGraph) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+
+    let mut sections: BTreeMap<&str, Vec<(&str, usize)>> = BTreeMap::new();
+    for page in graph.nodes() {
+        let inlinks = graph
+            .edges_directed(page, Direction::Incoming)
+            .filter(|&(source, _, &kind)| kind == "links" && source != page)
+            .count();
+        if inlinks > 0 {
+            let section = page.split_once('/').map_or("", |(section, _)| section);
+            sections.entry(section).or_default().push((page, inlinks));
+        }
+    }
+    let mut sections: Vec<_> = sections
+        .into_iter()
+        .map(|(section, mut pages)| {
+            pages.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            let inlinks: usize = pages.iter().map(|(_, inlinks)| inlinks).sum();
+            (section, inlinks, pages)
+        })
+        .collect();
+    sections.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let total: usize = sections.iter().map(|(_, inlinks, _)| inlinks).sum();
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.0} {h:.0}" font-family="monospace" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    )?;
+
+    let mut x = 0.0;
+    for (i, (section, inlinks, pages)) in sections.iter().enumerate() {
+        let width = WIDTH * *inlinks as f64 / total as f64;
+        let name = if section.is_empty() { "/" } else { section };
+        let fill = format!("hsl({}, 60%, 80%)", i * 47 % 360);
+        writeln!(
+            svg,
+            r#"<g><title>{} ({})</title><rect x="{:.1}" y="0" width="{:.1}" height="{:.1}" fill="{}" stroke="black"/>"#,
+            escape_xml(name),
+            inlinks,
+            x,
+            width,
+            HEIGHT,
+            fill
+        )?;
+        write_label(&mut svg, name, x, 0.0, width, HEADER)?;
+
+        let mut y = HEADER;
+        for (page, page_inlinks) in pages {
+            let height = (HEIGHT - HEADER) * *page_inlinks as f64 / *inlinks as f64;
+            writeln!(
+                svg,
+                r#"<g><title>{} ({})</title><rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="white" fill-opacity="0.5" stroke="black"/>"#,
+                escape_xml(page),
+                page_inlinks,
+                x,
+                y,
+                width,
+                height
+            )?;
+            write_label(&mut svg, page, x, y, width, height)?;
+            svg.push_str("</g>\n");
+            y += height;
+        }
+        svg.push_str("</g>\n");
+        x += width;
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Write `text` into the top left of the rectangle, if it fits.
+fn write_label(
+    svg: &mut String,
+    text: &str,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<(), anyhow::Error> {
+    if height >= HEADER && width >= (text.chars().count() + 1) as f64 * CHAR_WIDTH {
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" dominant-baseline="hanging">{}</text>"#,
+            x + CHAR_WIDTH / 2.0,
+            y + 2.0,
+            escape_xml(text)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use regex::Regex;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_well_formed_svg() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["blog/a", "blog/b", "q&a"]));
+        data.insert("blog/a".to_string(), page(&["blog/b", "index", "blog/a"]));
+        data.insert("lost".to_string(), page(&[]));
+        let page_graph = PageGraph::from(data);
+
+        let svg = render(&page_graph).unwrap();
+
+        // Every opened element is closed in order.
+        let tag = Regex::new(r"<(/?)([a-z]+)[^>]*?(/?)>").unwrap();
+        let mut open = Vec::new();
+        for tag in tag.captures_iter(&svg) {
+            match (&tag[1], &tag[3]) {
+                ("/", _) => assert_eq!(open.pop(), Some(tag[2].to_string())),
+                (_, "/") => {}
+                _ => open.push(tag[2].to_string()),
+            }
+        }
+        assert!(open.is_empty());
+        assert!(svg.starts_with("<svg "));
+
+        // Sections blog and /, pages blog/b, blog/a, index and q&a.
+        assert_eq!(svg.matches("<rect ").count(), 6);
+        assert!(svg.contains("<title>blog (3)</title>"));
+        assert!(svg.contains("<title>q&amp;a (1)</title>"));
+        assert!(!svg.contains("lost"));
+    }
+}
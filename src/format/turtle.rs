@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+use super::RenderOptions;
+use crate::PageGraph;
+
+/// Type given to every page, so pages without links still appear in the output.
+const PAGE_TYPE: &str = "https://schema.org/WebPage";
+
+/// Predicate from a page to its fragments.
+const PART_PREDICATE: &str = "https://schema.org/hasPart";
+
+/// Render `page_graph` as RDF Turtle, one `<source> <predicate> <target> .` triple per link,
+/// with `options.rdf_predicate`. Edges from a page to its fragments use `schema:hasPart`.
+/// Pages become IRIs below `options.base_url`.
+pub fn render(page_graph: &PageGraph, options: &RenderOptions) -> Result<String, anyhow::Error> {
+    let graph = page_graph.graph();
+    let iri = |page: &str| escape_iri(&format!("{}{}", options.base_url, page));
+
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable();
+    let mut edges: Vec<_> = graph.all_edges().collect();
+    edges.sort_unstable();
+
+    let mut turtle = String::new();
+    for node in nodes {
+        writeln!(turtle, "<{}> a <{}> .", iri(node), PAGE_TYPE)?;
+    }
+    let predicate = escape_iri(&options.rdf_predicate);
+    for (source, target, &relation) in edges {
+        let predicate = match relation {
+            "contains" => PART_PREDICATE,
+            _ => &predicate,
+        };
+        writeln!(
+            turtle,
+            "<{}> <{}> <{}> .",
+            iri(source),
+            predicate,
+            iri(target)
+        )?;
+    }
+    Ok(turtle)
+}
+
+/// Escape the characters Turtle does not allow inside `<...>` as `\uXXXX`.
+pub fn escape_iri(iri: &str) -> String {
+    let mut escaped = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        if c <= ' ' || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\') {
+            write!(escaped, "\\u{:04X}", c as u32).unwrap();
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use regex::Regex;
+    use std::collections::HashMap;
+
+    #[test]
+    fn renders_valid_triples() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["a b".to_string(), "c#top".to_string()],
+        );
+        data.insert("lonely".to_string(), vec![]);
+        let page_graph = PageGraph::from(data);
+
+        let options = RenderOptions {
+            base_url: "https://example.com/".to_string(),
+            rdf_predicate: "https://example.com/vocab#linksTo".to_string(),
+            ..RenderOptions::default()
+        };
+        let turtle = render(&page_graph, &options).unwrap();
+
+        let iri = r#"<(?:[^<>"{}|^`\\\x00-\x20]|\\u[0-9A-F]{4})*>"#;
+        let triple = Regex::new(&format!(r"^{iri} (a|{iri}) {iri} \.$", iri = iri)).unwrap();
+        assert!(
+            turtle.lines().all(|line| triple.is_match(line)),
+            "{}",
+            turtle
+        );
+
+        assert_eq!(turtle.lines().count(), 8);
+        assert!(turtle.contains(
+            "<https://example.com/index> <https://example.com/vocab#linksTo> <https://example.com/a\\u0020b> ."
+        ));
+        assert!(turtle.contains("<https://example.com/lonely> a <https://schema.org/WebPage> ."));
+        assert!(turtle.contains(
+            "<https://example.com/c> <https://schema.org/hasPart> <https://example.com/c#top> ."
+        ));
+        assert!(!turtle.contains("<https://example.com/c> <https://example.com/vocab#linksTo>"));
+    }
+}
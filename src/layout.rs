@@ -0,0 +1,132 @@
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use std::collections::HashMap;
+
+use crate::depths;
+
+/// Approximate width of one label character, in pixels.
+pub const CHAR_WIDTH: f64 = 7.0;
+/// Height of a node box, in pixels.
+pub const NODE_HEIGHT: f64 = 24.0;
+/// Horizontal padding inside a node box and gap between boxes, in pixels.
+const PADDING: f64 = 16.0;
+/// Vertical distance between two layers, in pixels.
+const LAYER_GAP: f64 = 80.0;
+
+/// A box for one node, positioned by its center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+}
+
+/// Node positions of a graph drawing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout<'a> {
+    pub nodes: HashMap<&'a str, NodeBox>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Lay out `graph` in horizontal layers by click depth from `root`,
+/// with unreachable pages in one extra layer at the bottom.
+/// Within a layer, nodes are ordered by the mean position of their parents in the layer above,
+/// which avoids many crossings in tree-like sites.
+pub fn layered<'a>(graph: &GraphMap<&'a str, &'a str, Directed>, root: &'a str) -> Layout<'a> {
+    let depths = depths(graph, root);
+    let unreachable = depths.values().max().map_or(0, |max| max + 1);
+
+    let mut layers: Vec<Vec<&str>> = Vec::new();
+    for node in graph.nodes() {
+        let layer = depths.get(node).copied().unwrap_or(unreachable);
+        if layers.len() <= layer {
+            layers.resize(layer + 1, Vec::new());
+        }
+        layers[layer].push(node);
+    }
+
+    let mut nodes: HashMap<&str, NodeBox> = HashMap::new();
+    let mut width: f64 = 0.0;
+    for (depth, layer) in layers.iter_mut().enumerate() {
+        // Order by parent barycenter, then by name so the drawing is stable.
+        let barycenter = |node: &str| {
+            let parents: Vec<f64> = graph
+                .neighbors_directed(node, Direction::Incoming)
+                .filter_map(|p| nodes.get(p).map(|b| b.x))
+                .collect();
+            if parents.is_empty() {
+                f64::MAX
+            } else {
+                parents.iter().sum::<f64>() / parents.len() as f64
+            }
+        };
+        let mut keyed: Vec<_> = layer.iter().map(|&n| (barycenter(n), n)).collect();
+        keyed.sort_by(|(a, na), (b, nb)| a.total_cmp(b).then_with(|| na.cmp(nb)));
+        *layer = keyed.into_iter().map(|(_, n)| n).collect();
+
+        let mut x = PADDING;
+        for &node in layer.iter() {
+            let node_width = label_width(node);
+            nodes.insert(
+                node,
+                NodeBox {
+                    x: x + node_width / 2.0,
+                    y: PADDING + NODE_HEIGHT / 2.0 + depth as f64 * LAYER_GAP,
+                    width: node_width,
+                },
+            );
+            x += node_width + PADDING;
+        }
+        width = width.max(x);
+    }
+
+    let height = if layers.is_empty() {
+        0.0
+    } else {
+        2.0 * PADDING + NODE_HEIGHT + (layers.len() - 1) as f64 * LAYER_GAP
+    };
+    Layout {
+        nodes,
+        width,
+        height,
+    }
+}
+
+/// Width of the box for a node labelled `label`.
+pub fn label_width(label: &str) -> f64 {
+    label.chars().count() as f64 * CHAR_WIDTH + PADDING
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+
+    #[test]
+    fn lays_out_by_depth() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["c".to_string()]);
+        data.insert("orphan".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+
+        let layout = layered(&graph, "index");
+
+        let y = |n| layout.nodes[n].y;
+        assert_eq!(y("a"), y("b"));
+        assert!(y("index") < y("a"));
+        assert!(y("a") < y("c"));
+        assert!(y("c") < y("orphan"));
+        assert!(layout.nodes["a"].x < layout.nodes["b"].x);
+
+        // Boxes in a layer don't overlap.
+        let (a, b) = (layout.nodes["a"], layout.nodes["b"]);
+        assert!(a.x + a.width / 2.0 <= b.x - b.width / 2.0);
+        assert!(layout
+            .nodes
+            .values()
+            .all(|n| n.x + n.width / 2.0 <= layout.width));
+    }
+}
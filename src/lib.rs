@@ -0,0 +1,2902 @@
+use anyhow::Context;
+
+use lazy_static::lazy_static;
+
+use petgraph::graphmap::GraphMap;
+use petgraph::*;
+
+use petgraph::visit::{Dfs, Reversed};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use regex::Regex;
+
+use extract::LinkExtractor;
+use resources::LinkKind;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{copy, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+pub mod analysis;
+pub mod anonymize;
+pub mod archive;
+pub mod budget;
+pub mod events;
+pub mod extract;
+pub mod format;
+pub mod layout;
+pub mod manifest;
+pub mod markdown;
+pub mod resources;
+pub mod sitemap;
+pub mod spill;
+pub mod stats;
+pub mod traffic;
+pub mod urls;
+
+lazy_static! {
+    // The regex crate runs in time linear in the input, without backtracking, so the lazy `*?`
+    // is no risk even for huge malformed tags, see `extracts_from_huge_malformed_tags`.
+    static ref URL: Regex =
+        Regex::new(r###"<a[^>]*?href\s*=\s*(?:"([^"]*)"|'([^']*)')[^>]*?>"###).unwrap();
+}
+
+lazy_static! {
+    static ref ANCHOR_BOUNDARY: Regex = Regex::new(r###"(?i)<\s*/?\s*a\b"###).unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r###"<[^>]*>"###).unwrap();
+    static ref NUMERIC_ENTITY: Regex =
+        Regex::new(r###"&#(?:([0-9]{1,7})|[xX]([0-9a-fA-F]{1,6}));"###).unwrap();
+}
+
+lazy_static! {
+    static ref SCHEME: Regex = Regex::new(r###"^[a-zA-Z][a-zA-Z0-9+.-]*:"###).unwrap();
+}
+
+lazy_static! {
+    static ref TITLE: Regex = Regex::new(r###"(?is)<title[^>]*>(.*?)</title>"###).unwrap();
+}
+
+lazy_static! {
+    static ref TRAPL_PREFIXES: Regex =
+        Regex::new(r###"(?i:http[s]?://www.traplinked.com/)(en/|nl/)?"###).unwrap();
+}
+
+lazy_static! {
+    static ref FILTER_TRAPL_URLS: Regex = Regex::new(r###"(?i).*traplinked.*"###).unwrap();
+}
+
+/// Settings for turning crawled pages into a page graph.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Host of the site, root-relative and protocol-relative links are resolved against it.
+    pub domain: String,
+    /// Only links matching this regex are kept.
+    pub filter: Regex,
+    /// Matches of this regex are stripped from kept links, leaving the page name.
+    pub prefix: Regex,
+    /// Keep trailing slashes, for sites where `page` and `page/` are different pages.
+    pub keep_trailing_slash: bool,
+    /// Treat `page/index.html` as `page/`.
+    pub collapse_index: bool,
+    /// Keep `page#section` as a node contained in `page` instead of stripping the fragment.
+    pub track_fragments: bool,
+    /// Drop query strings, so `page?ref=x` is `page`.
+    pub strip_query: bool,
+    /// Leave out links search engines don't follow, see `UNFOLLOWED_RELS`.
+    pub follow_only: bool,
+    /// Record the hosts of off-domain `http(s)` links, see `PageGraph::with_external_nodes`.
+    pub include_external: bool,
+    /// Drop links whose `Link::text` is one of these, compared case-insensitively.
+    /// Meant for boilerplate like "Back to top".
+    pub drop_anchor_texts: Vec<String>,
+    /// Leave out pages with `<meta name="robots" content="noindex">` and the links of pages
+    /// with `nofollow`, as search engines do.
+    pub respect_meta_robots: bool,
+    /// Record the `id`s of pages and the fragment links pointing at them,
+    /// see `PageGraph::broken_anchors`.
+    pub check_anchors: bool,
+    /// Keep links from pages to themselves, like `href="?page=2"` normalized without the query.
+    /// They are dropped otherwise, see `PageGraph::dropped_self_links`.
+    pub keep_self_links: bool,
+    /// Record the text of each kept link, see `PageGraph::link_text_variants`.
+    pub record_link_texts: bool,
+    /// Spell percent-encoding of page paths the same way, so `caf%C3%A9` and `café` are one
+    /// page. Page names and links are left as they are if `None`.
+    pub percent_mode: Option<PercentMode>,
+    /// How links are found in a page, by file extension.
+    pub extractors: extract::Extractors,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            domain: "www.traplinked.com".to_string(),
+            filter: FILTER_TRAPL_URLS.clone(),
+            prefix: TRAPL_PREFIXES.clone(),
+            keep_trailing_slash: false,
+            collapse_index: false,
+            track_fragments: false,
+            strip_query: false,
+            follow_only: false,
+            include_external: false,
+            drop_anchor_texts: Vec::new(),
+            respect_meta_robots: false,
+            check_anchors: false,
+            keep_self_links: false,
+            record_link_texts: false,
+            percent_mode: None,
+            extractors: extract::Extractors::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Keep links to `domain` only, naming pages by the path after the host.
+    /// Hosts are compared case-insensitively, paths are not.
+    pub fn for_domain(domain: &str) -> Self {
+        let host = regex::escape(domain);
+        Self {
+            domain: domain.to_string(),
+            filter: Regex::new(&format!(r"^(?i:https?://{})(?:[/?#]|$)", host)).unwrap(),
+            prefix: Regex::new(&format!(r"^(?i:https?://{})/?", host)).unwrap(),
+            ..Self::default()
+        }
+    }
+
+    /// Also keep links to `host`, naming its pages the same as on the configured domain, and
+    /// resolve relative links against `host` from now on. This merges crawls of different
+    /// environments, like `staging.example.com` and `www.example.com`. Other hosts stay dropped.
+    pub fn with_canonical_host(self, host: &str) -> Self {
+        let escaped = regex::escape(host);
+        let filter = format!(r"(?:{})|^(?i:https?://{})(?:[/?#]|$)", self.filter, escaped);
+        let prefix = format!(r"(?:{})|^(?i:https?://{})/?", self.prefix, escaped);
+        Self {
+            domain: host.to_string(),
+            filter: Regex::new(&filter).unwrap(),
+            prefix: Regex::new(&prefix).unwrap(),
+            ..self
+        }
+    }
+
+    /// Make `url`, found on `page`, absolute if it is protocol-relative (`//host/page`),
+    /// root-relative (`/page`) or relative to `page` (`../page`).
+    pub fn absolute(&self, page: &str, url: &str) -> String {
+        if url.starts_with("//") {
+            format!("https:{}", url)
+        } else if url.starts_with('/') {
+            format!("https://{}{}", self.domain, url)
+        } else if SCHEME.is_match(url) {
+            url.to_string()
+        } else {
+            format!("https://{}/{}", self.domain, resolve_relative(page, url))
+        }
+    }
+}
+
+/// Pages and the pages they link to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageGraph {
+    links: HashMap<String, Vec<String>>,
+    titles: HashMap<String, String>,
+    placeholders: HashMap<String, usize>,
+    fragments: HashMap<String, BTreeSet<String>>,
+    modified: HashMap<String, SystemTime>,
+    unfollowed: HashMap<(String, String), BTreeSet<String>>,
+    external_links: HashMap<String, BTreeSet<String>>,
+    external_nodes: BTreeSet<String>,
+    noindex: BTreeSet<String>,
+    nofollow: BTreeSet<String>,
+    anchor_targets: HashMap<String, HashSet<String>>,
+    fragment_links: BTreeSet<(String, String, String)>,
+    dropped_self_links: HashMap<String, usize>,
+    link_texts: BTreeSet<(String, String, String)>,
+}
+
+impl PageGraph {
+    /// Maps page names to the page names they link to.
+    pub fn links(&self) -> &HashMap<String, Vec<String>> {
+        &self.links
+    }
+
+    /// Maps (page, linked page) to how often the page links to it.
+    pub fn link_counts(&self) -> HashMap<(&str, &str), usize> {
+        let mut counts = HashMap::new();
+        for (page, links) in &self.links {
+            for link in links {
+                *counts.entry((page.as_str(), link.as_str())).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    /// The `<title>` of a crawled page, if it has one.
+    pub fn title(&self, page: &str) -> Option<&str> {
+        self.titles.get(page).map(String::as_str)
+    }
+
+    /// Maps crawled pages to how many placeholder links like `href="#"` they contain,
+    /// leaving out pages without any.
+    pub fn placeholder_links(&self) -> &HashMap<String, usize> {
+        &self.placeholders
+    }
+
+    /// Maps linked pages to the distinct fragments links to them pointed at,
+    /// collected while fragments are stripped.
+    pub fn fragments(&self) -> &HashMap<String, BTreeSet<String>> {
+        &self.fragments
+    }
+
+    /// Maps (page, linked page) to the `UNFOLLOWED_RELS` of the links between them, for those
+    /// where every link has one. These edges are missing from `links` with `Config::follow_only`.
+    pub fn unfollowed(&self) -> &HashMap<(String, String), BTreeSet<String>> {
+        &self.unfollowed
+    }
+
+    /// Whether search engines follow the link from `page` to `target`, i.e. it is not unfollowed.
+    pub fn is_followed(&self, page: &str, target: &str) -> bool {
+        !self
+            .unfollowed
+            .contains_key(&(page.to_string(), target.to_string()))
+    }
+
+    /// The (page, linked page, fragment) of links pointing into a page of the site, including
+    /// `#section` links within a page, whose fragment is not an `id`, or `<a name>`, of the
+    /// linked page. Links to pages that were not crawled or are markdown are left out, as the
+    /// targets of those aren't known. Empty unless built with `Config::check_anchors`.
+    pub fn broken_anchors(&self) -> Vec<(&str, &str, &str)> {
+        self.fragment_links
+            .iter()
+            .filter(|(_, target, fragment)| {
+                self.anchor_targets.get(target).is_some_and(|targets| {
+                    // Browsers scroll to the top for `#top` without a matching element.
+                    !targets.contains(fragment) && !fragment.eq_ignore_ascii_case("top")
+                })
+            })
+            .map(|(page, target, fragment)| (page.as_str(), target.as_str(), fragment.as_str()))
+            .collect()
+    }
+
+    /// Linked pages with at least `min` different link texts, and those texts, sorted. Pages
+    /// with the most variants come first. Empty unless built with `Config::record_link_texts`.
+    pub fn link_text_variants(&self, min: usize) -> Vec<(&str, Vec<&str>)> {
+        let mut texts: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (_, target, text) in &self.link_texts {
+            texts.entry(target).or_default().insert(text);
+        }
+        let mut variants: Vec<_> = texts
+            .into_iter()
+            .filter(|(_, texts)| texts.len() >= min)
+            .map(|(target, texts)| (target, texts.into_iter().collect::<Vec<_>>()))
+            .collect();
+        variants.sort_by_key(|(_, texts)| std::cmp::Reverse(texts.len()));
+        variants
+    }
+
+    /// The (page, linked page) of links to pages of the site which weren't crawled, sorted. When
+    /// the whole site was read these are broken links, found without any requests. Links to
+    /// other hosts aren't pages of the site, so they aren't among them.
+    pub fn missing_link_targets(&self) -> Vec<(&str, &str)> {
+        let mut missing: Vec<_> = self
+            .links
+            .iter()
+            .flat_map(|(page, links)| links.iter().map(move |link| (page.as_str(), link.as_str())))
+            .filter(|(_, link)| {
+                let (target, _) = split_fragment(link);
+                !self.links.contains_key(target)
+            })
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        missing
+    }
+
+    /// Maps pages to how many links to themselves they had, left out of `links` unless built
+    /// with `Config::keep_self_links`. These are mostly pagination and anchor links.
+    pub fn dropped_self_links(&self) -> &HashMap<String, usize> {
+        &self.dropped_self_links
+    }
+
+    /// Pages linking to themselves, whether those links were dropped or kept, sorted.
+    pub fn self_link_pages(&self) -> Vec<&str> {
+        let kept = self
+            .links
+            .iter()
+            .filter(|(page, links)| links.contains(page))
+            .map(|(page, _)| page.as_str());
+        let mut pages: Vec<_> = self
+            .dropped_self_links
+            .keys()
+            .map(String::as_str)
+            .chain(kept)
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+
+    /// Pages left out for their `noindex` meta robots, with `Config::respect_meta_robots`.
+    pub fn noindex_pages(&self) -> &BTreeSet<String> {
+        &self.noindex
+    }
+
+    /// Pages whose links were left out for their `nofollow` meta robots,
+    /// with `Config::respect_meta_robots`.
+    pub fn nofollow_pages(&self) -> &BTreeSet<String> {
+        &self.nofollow
+    }
+
+    /// Maps crawled pages to the other hosts they link to, with `Config::include_external`.
+    pub fn external_links(&self) -> &HashMap<String, BTreeSet<String>> {
+        &self.external_links
+    }
+
+    /// Whether `node` is another host, added by `with_external_nodes`.
+    pub fn is_external(&self, node: &str) -> bool {
+        self.external_nodes.contains(node)
+    }
+
+    /// A copy with the hosts of `external_links` as extra nodes, which pages link to but which
+    /// have no links themselves. Analysis should use the graph without them, as they would be
+    /// orphans and dead ends.
+    pub fn with_external_nodes(&self) -> Self {
+        let mut graph = self.clone();
+        for (page, hosts) in &self.external_links {
+            let links = graph.links.entry(page.clone()).or_default();
+            for host in hosts {
+                if !self.links.contains_key(host) {
+                    links.push(host.clone());
+                    graph.external_nodes.insert(host.clone());
+                }
+            }
+        }
+        graph
+    }
+
+    /// A copy with only the given `pages` and the links between them, e.g. one component.
+    pub fn restricted_to(&self, pages: &HashSet<&str>) -> Self {
+        let keep = |page: &String| pages.contains(page.as_str());
+        let mut graph = self.clone();
+        graph.links.retain(|page, _| keep(page));
+        for links in graph.links.values_mut() {
+            links.retain(keep);
+        }
+        graph.titles.retain(|page, _| keep(page));
+        graph.placeholders.retain(|page, _| keep(page));
+        graph.fragments.retain(|page, _| keep(page));
+        graph.modified.retain(|page, _| keep(page));
+        graph
+            .unfollowed
+            .retain(|(page, link), _| keep(page) && keep(link));
+        graph.external_links.retain(|page, _| keep(page));
+        graph.external_nodes.retain(keep);
+        graph.noindex.retain(keep);
+        graph.nofollow.retain(keep);
+        graph.anchor_targets.retain(|page, _| keep(page));
+        graph
+            .fragment_links
+            .retain(|(page, link, _)| keep(page) && keep(link));
+        graph.dropped_self_links.retain(|page, _| keep(page));
+        graph
+            .link_texts
+            .retain(|(page, link, _)| keep(page) && keep(link));
+        graph
+    }
+
+    /// Maps the pages and link targets matching `pagination`, like `blog/page/2`, to the page
+    /// named without the matches, like `blog`. Names which would be left empty are kept.
+    pub fn pagination_parents(&self, pagination: &Regex) -> HashMap<String, String> {
+        self.graph()
+            .nodes()
+            .filter_map(|page| {
+                let parent = pagination.replace_all(page, "");
+                if parent.is_empty() || parent == page {
+                    None
+                } else {
+                    Some((page.to_string(), parent.into_owned()))
+                }
+            })
+            .collect()
+    }
+
+    /// A copy with each page in `into` merged into the page it maps to, which gets its links
+    /// and the links to it, e.g. from `pagination_parents`. Links which only become links of a
+    /// page to itself by merging are left out and counted in `dropped_self_links`.
+    pub fn with_pages_merged(&self, into: &HashMap<String, String>) -> Self {
+        let rename = |page: String| into.get(&page).cloned().unwrap_or(page);
+        let merged_name = |page: &String| into.get(page).unwrap_or(page).clone();
+        let graph = self.clone();
+        let mut merged = Self {
+            external_nodes: graph.external_nodes,
+            ..Self::default()
+        };
+        for (page, links) in graph.links {
+            let before = links.len();
+            let links: Vec<_> = links
+                .into_iter()
+                .filter(|link| *link == page || merged_name(link) != merged_name(&page))
+                .map(rename)
+                .collect();
+            let page = rename(page);
+            let dropped = before - links.len();
+            if dropped > 0 {
+                *merged.dropped_self_links.entry(page.clone()).or_default() += dropped;
+            }
+            merged.links.entry(page).or_default().extend(links);
+        }
+        for (page, count) in graph.dropped_self_links {
+            *merged.dropped_self_links.entry(rename(page)).or_default() += count;
+        }
+        // Sorted, so the parent keeps its own title before those of merged pages.
+        let mut titles: Vec<_> = graph.titles.into_iter().collect();
+        titles.sort_unstable_by_key(|(page, _)| into.contains_key(page));
+        for (page, title) in titles {
+            merged.titles.entry(rename(page)).or_insert(title);
+        }
+        for (page, count) in graph.placeholders {
+            *merged.placeholders.entry(rename(page)).or_default() += count;
+        }
+        for (page, fragments) in graph.fragments {
+            merged
+                .fragments
+                .entry(rename(page))
+                .or_default()
+                .extend(fragments);
+        }
+        for (page, time) in graph.modified {
+            let modified = merged.modified.entry(rename(page)).or_insert(time);
+            *modified = (*modified).max(time);
+        }
+        for ((page, link), rels) in graph.unfollowed {
+            let (page, link) = (rename(page), rename(link));
+            if page != link {
+                merged
+                    .unfollowed
+                    .entry((page, link))
+                    .or_default()
+                    .extend(rels);
+            }
+        }
+        for (page, hosts) in graph.external_links {
+            merged
+                .external_links
+                .entry(rename(page))
+                .or_default()
+                .extend(hosts);
+        }
+        merged.noindex = graph.noindex.into_iter().map(rename).collect();
+        merged.nofollow = graph.nofollow.into_iter().map(rename).collect();
+        for (page, targets) in graph.anchor_targets {
+            merged
+                .anchor_targets
+                .entry(rename(page))
+                .or_default()
+                .extend(targets);
+        }
+        merged.fragment_links = graph
+            .fragment_links
+            .into_iter()
+            .map(|(page, link, fragment)| (rename(page), rename(link), fragment))
+            .collect();
+        merged.link_texts = graph
+            .link_texts
+            .into_iter()
+            .map(|(page, link, text)| (rename(page), rename(link), text))
+            .filter(|(page, link, _)| page != link)
+            .collect();
+        merged
+    }
+
+    /// When the source file of a crawled page was last modified, if known.
+    pub fn modified(&self, page: &str) -> Option<SystemTime> {
+        self.modified.get(page).copied()
+    }
+
+    /// Record the `<title>`s of pages.
+    pub fn with_titles(mut self, titles: HashMap<String, String>) -> Self {
+        self.titles.extend(titles);
+        self
+    }
+
+    /// Combine with `other`, e.g. a saved graph of an earlier crawl. Links are added up, so a
+    /// link found in both counts twice; titles of this graph win and the later modification
+    /// time wins. Placeholder and dropped self-link counts add up too, and the other findings
+    /// about pages are united.
+    pub fn merged_with(mut self, other: PageGraph) -> Self {
+        for (page, links) in other.links {
+            self.links.entry(page).or_default().extend(links);
+        }
+        for (page, title) in other.titles {
+            self.titles.entry(page).or_insert(title);
+        }
+        for (page, count) in other.placeholders {
+            *self.placeholders.entry(page).or_default() += count;
+        }
+        for (page, fragments) in other.fragments {
+            self.fragments.entry(page).or_default().extend(fragments);
+        }
+        for (page, time) in other.modified {
+            let modified = self.modified.entry(page).or_insert(time);
+            *modified = (*modified).max(time);
+        }
+        for (link, rels) in other.unfollowed {
+            self.unfollowed.entry(link).or_default().extend(rels);
+        }
+        for (page, hosts) in other.external_links {
+            self.external_links.entry(page).or_default().extend(hosts);
+        }
+        self.external_nodes.extend(other.external_nodes);
+        self.noindex.extend(other.noindex);
+        self.nofollow.extend(other.nofollow);
+        for (page, targets) in other.anchor_targets {
+            self.anchor_targets.entry(page).or_default().extend(targets);
+        }
+        self.fragment_links.extend(other.fragment_links);
+        for (page, count) in other.dropped_self_links {
+            *self.dropped_self_links.entry(page).or_default() += count;
+        }
+        self.link_texts.extend(other.link_texts);
+        self
+    }
+
+    /// Record the other hosts pages link to, see `external_links`.
+    pub fn with_external_links(mut self, external: HashMap<String, BTreeSet<String>>) -> Self {
+        for (page, hosts) in external {
+            self.external_links.entry(page).or_default().extend(hosts);
+        }
+        self
+    }
+
+    /// Record the last-modified times of the source files of pages.
+    pub fn with_modified(mut self, modified: HashMap<String, SystemTime>) -> Self {
+        self.modified.extend(modified);
+        self
+    }
+
+    /// Make a petgraph `GraphMap` borrowing the page names.
+    /// Fragment nodes `page#section` get an edge labelled "contains" from `page`, unless `page`
+    /// links to the fragment itself, which keeps the "links" edge.
+    pub fn graph(&self) -> GraphMap<&str, &str, Directed> {
+        let mut graph = make_page_graph(&self.links);
+
+        let fragments: Vec<_> = graph.nodes().filter(|n| n.contains('#')).collect();
+        for fragment in fragments {
+            let (page, _) = fragment.split_once('#').unwrap();
+            if !page.is_empty() && !graph.contains_edge(page, fragment) {
+                graph.add_edge(page, fragment, "contains");
+            }
+        }
+        graph
+    }
+}
+
+impl From<HashMap<String, Vec<String>>> for PageGraph {
+    fn from(links: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            links,
+            ..Self::default()
+        }
+    }
+}
+
+/// The links of `page_graph` as a JSON object mapping page names to the page names they link to,
+/// in order and as often as found. For `--dump-links`, to debug link extraction and to build
+/// graphs again without crawling, see `load_links`.
+pub fn dump_links(page_graph: &PageGraph) -> Result<String, serde_json::Error> {
+    let links: BTreeMap<_, _> = page_graph.links().iter().collect();
+    serde_json::to_string_pretty(&links)
+}
+
+/// Read links written by `dump_links` into a page graph, without titles or other page details.
+pub fn load_links(json: &str) -> Result<PageGraph, serde_json::Error> {
+    Ok(PageGraph::from(serde_json::from_str::<
+        HashMap<String, Vec<String>>,
+    >(json)?))
+}
+
+/// Build the graph of a single page named `name`, reading its html from `reader`.
+/// Meant for stdin, like `curl https://example.com/ | page-graph --stdin`.
+pub fn build_graph_from_reader(
+    name: &str,
+    mut reader: impl Read,
+    config: &Config,
+) -> Result<PageGraph, anyhow::Error> {
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .context(format!("Could not read {}", name))?;
+    Ok(build_graph_from_pages(
+        vec![(name.to_string(), content)],
+        config,
+    ))
+}
+
+/// Build a page graph from (page name, html) pairs.
+/// A page appearing twice replaces the links of its earlier occurrence.
+pub fn build_graph_from_pages(
+    pages: impl IntoIterator<Item = (String, String)>,
+    config: &Config,
+) -> PageGraph {
+    build_graph_from_pages_with(pages, config, |_, _, _| {})
+}
+
+/// Like `build_graph_from_pages`, calling `on_link(page, target, kind)` for every kept link.
+/// Links to a directory whose index page was crawled, like `products/` when there is a
+/// `products/index.html`, link to the index page in the graph, but `target` is the directory.
+///
+/// Pages are processed in the order given, and the links of each page in the order they
+/// appear in it, so all calls for one page happen before any for the next. A link appearing
+/// several times on a page is reported each time, although the graph has a single edge for it.
+pub fn build_graph_from_pages_with(
+    pages: impl IntoIterator<Item = (String, String)>,
+    config: &Config,
+    mut on_link: impl FnMut(&str, &str, LinkKind),
+) -> PageGraph {
+    let mut builder = GraphBuilder::new(config);
+    for (name, content) in pages {
+        builder.add_page_with(name, content, &mut on_link);
+    }
+    builder.finish()
+}
+
+/// Builds a page graph a page at a time, for adding pages as they are read. Pages added twice
+/// replace their earlier links, like in `build_graph_from_pages`. As a `PageSink`, pages are
+/// built as they are pushed, so the html doesn't need to be kept.
+pub struct GraphBuilder<'a> {
+    graph: PageGraph,
+    config: &'a Config,
+    pages: usize,
+}
+
+impl<'a> GraphBuilder<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            graph: PageGraph::default(),
+            config,
+            pages: 0,
+        }
+    }
+
+    /// Add the page `name` with the html `content`.
+    pub fn add_page(&mut self, name: String, content: String) {
+        self.add_page_with(name, content, &mut |_, _, _| {})
+    }
+
+    /// Like `add_page`, calling `on_link` like `build_graph_from_pages_with`.
+    pub fn add_page_with(
+        &mut self,
+        name: String,
+        content: String,
+        on_link: &mut dyn FnMut(&str, &str, LinkKind),
+    ) {
+        let (graph, config) = (&mut self.graph, self.config);
+        self.pages += 1;
+        let name = match config.percent_mode {
+            Some(mode) => normalize_percent_encoding(&name, mode),
+            None => name,
+        };
+        let _page = tracing::info_span!("page", page = %name).entered();
+        let parse = tracing::info_span!("parse").entered();
+        match get_title_from(&content) {
+            Some(title) => graph.titles.insert(name.clone(), title),
+            None => graph.titles.remove(&name),
+        };
+
+        if graph.links.contains_key(&name) {
+            graph.unfollowed.retain(|(page, _), _| *page != name);
+        }
+        let robots = if config.respect_meta_robots {
+            resources::meta_robots(&content)
+        } else {
+            resources::MetaRobots::default()
+        };
+        for (pages, flagged) in [
+            (&mut graph.noindex, robots.noindex),
+            (&mut graph.nofollow, robots.nofollow),
+        ] {
+            if flagged {
+                pages.insert(name.clone());
+            } else {
+                pages.remove(&name);
+            }
+        }
+
+        if config.record_link_texts {
+            graph.link_texts.retain(|(page, _, _)| *page != name);
+        }
+        if config.check_anchors {
+            graph.fragment_links.retain(|(page, _, _)| *page != name);
+            if markdown::is_markdown(&name) {
+                graph.anchor_targets.remove(&name);
+            } else {
+                let targets = resources::anchor_targets(&content);
+                graph.anchor_targets.insert(name.clone(), targets);
+            }
+        }
+
+        let mut links = Vec::new();
+        let mut placeholders = 0;
+        let mut followed = HashSet::new();
+        let mut unfollowed: HashMap<String, BTreeSet<String>> = HashMap::new();
+        let mut external = BTreeSet::new();
+        let raw_links = if robots.nofollow {
+            Vec::new()
+        } else {
+            config.extractors.links(&name, &content)
+        };
+        drop(parse);
+
+        let _normalize = tracing::info_span!("normalize").entered();
+        for raw in raw_links {
+            let url = raw.href.as_ref();
+            match normalize_link(&name, url, config) {
+                Ok(_) if drops_anchor_text(&raw, config) => {}
+                Ok(link) => {
+                    let rels = raw.unfollowed_rels();
+                    if rels.is_empty() {
+                        followed.insert(link.clone());
+                    } else {
+                        unfollowed.entry(link.clone()).or_default().extend(rels);
+                        if config.follow_only {
+                            continue;
+                        }
+                    }
+                    if let (_, Some(fragment)) = split_fragment(&config.absolute(&name, url)) {
+                        if !fragment.is_empty() {
+                            if !config.track_fragments {
+                                graph
+                                    .fragments
+                                    .entry(link.clone())
+                                    .or_default()
+                                    .insert(fragment.to_string());
+                            }
+                            if config.check_anchors {
+                                let (target, _) = split_fragment(&link);
+                                graph.fragment_links.insert((
+                                    name.clone(),
+                                    target.to_string(),
+                                    fragment.to_string(),
+                                ));
+                            }
+                        }
+                    }
+                    if config.record_link_texts {
+                        let text = raw.text();
+                        if !text.is_empty() {
+                            graph.link_texts.insert((name.clone(), link.clone(), text));
+                        }
+                    }
+                    on_link(&name, &link, LinkKind::Anchor);
+                    links.push(link)
+                }
+                Err(DropReason::Placeholder) => placeholders += 1,
+                Err(DropReason::InPage) if config.check_anchors => {
+                    let fragment = &url.trim()[1..];
+                    graph
+                        .fragment_links
+                        .insert((name.clone(), name.clone(), fragment.to_string()));
+                }
+                Err(DropReason::OffDomain) if config.include_external => {
+                    if config.follow_only && !raw.unfollowed_rels().is_empty() {
+                        continue;
+                    }
+                    if let Some(host) = external_host(&config.absolute(&name, url)) {
+                        external.insert(host);
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+
+        for (link, rels) in unfollowed {
+            if !followed.contains(&link) {
+                graph.unfollowed.insert((name.clone(), link), rels);
+            }
+        }
+        if !external.is_empty() {
+            graph.external_links.insert(name.clone(), external);
+        } else {
+            graph.external_links.remove(&name);
+        }
+        if placeholders > 0 {
+            graph.placeholders.insert(name.clone(), placeholders);
+        } else {
+            graph.placeholders.remove(&name);
+        }
+        graph.links.insert(name, links);
+    }
+
+    /// The graph of the pages added, with links to directories resolved to their index pages.
+    pub fn finish(self) -> PageGraph {
+        let mut graph = self.graph;
+        let config = self.config;
+
+        let index_pages: HashMap<String, String> = graph
+            .links
+            .values()
+            .flatten()
+            .filter(|link| !graph.links.contains_key(*link))
+            .filter_map(|link| Some((link.clone(), index_page(link, &graph.links)?)))
+            .collect();
+        for link in graph.links.values_mut().flatten() {
+            if let Some(page) = index_pages.get(link) {
+                *link = page.clone();
+            }
+        }
+        for (directory, page) in &index_pages {
+            if let Some(fragments) = graph.fragments.remove(directory) {
+                graph
+                    .fragments
+                    .entry(page.clone())
+                    .or_default()
+                    .extend(fragments);
+            }
+        }
+        if !config.keep_self_links {
+            graph.dropped_self_links.clear();
+            for (page, links) in &mut graph.links {
+                let before = links.len();
+                links.retain(|link| link != page);
+                if links.len() < before {
+                    graph
+                        .dropped_self_links
+                        .insert(page.clone(), before - links.len());
+                }
+            }
+            graph.unfollowed.retain(|(page, link), _| page != link);
+        }
+        graph.link_texts = std::mem::take(&mut graph.link_texts)
+            .into_iter()
+            .map(|(page, link, text)| {
+                let link = index_pages.get(&link).cloned().unwrap_or(link);
+                (page, link, text)
+            })
+            .filter(|(page, link, _)| config.keep_self_links || page != link)
+            .collect();
+        graph.fragment_links = std::mem::take(&mut graph.fragment_links)
+            .into_iter()
+            .map(|(page, link, fragment)| {
+                let link = index_pages.get(&link).cloned().unwrap_or(link);
+                (page, link, fragment)
+            })
+            .collect();
+        graph.unfollowed = std::mem::take(&mut graph.unfollowed)
+            .into_iter()
+            .map(|((page, link), rels)| {
+                let link = index_pages.get(&link).cloned().unwrap_or(link);
+                ((page, link), rels)
+            })
+            .collect();
+
+        if !graph.noindex.is_empty() {
+            let noindex = &graph.noindex;
+            graph.links.retain(|page, _| !noindex.contains(page));
+            for links in graph.links.values_mut() {
+                links.retain(|link| !noindex.contains(link));
+            }
+            graph.titles.retain(|page, _| !noindex.contains(page));
+            graph.placeholders.retain(|page, _| !noindex.contains(page));
+            graph.fragments.retain(|page, _| !noindex.contains(page));
+            graph
+                .dropped_self_links
+                .retain(|page, _| !noindex.contains(page));
+            graph
+                .external_links
+                .retain(|page, _| !noindex.contains(page));
+            graph
+                .unfollowed
+                .retain(|(page, link), _| !noindex.contains(page) && !noindex.contains(link));
+            graph
+                .fragment_links
+                .retain(|(page, link, _)| !noindex.contains(page) && !noindex.contains(link));
+            graph
+                .link_texts
+                .retain(|(page, link, _)| !noindex.contains(page) && !noindex.contains(link));
+        }
+        graph
+    }
+}
+
+impl PageSink for GraphBuilder<'_> {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        self.add_page(name, content);
+        Ok(())
+    }
+
+    fn page_count(&self) -> usize {
+        self.pages
+    }
+}
+
+/// Whether `link` has one of the `Config::drop_anchor_texts`.
+fn drops_anchor_text(link: &Link, config: &Config) -> bool {
+    if config.drop_anchor_texts.is_empty() {
+        return false;
+    }
+    let text = link.text().to_lowercase();
+    config
+        .drop_anchor_texts
+        .iter()
+        .any(|drop| drop.to_lowercase() == text)
+}
+
+/// The lowercase host of an absolute `http(s)` `url`, e.g. `github.com`.
+fn external_host(url: &str) -> Option<String> {
+    let lowercase = url.to_ascii_lowercase();
+    let rest = lowercase
+        .strip_prefix("https://")
+        .or_else(|| lowercase.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap();
+    let host = host.rsplit('@').next().unwrap();
+    Some(host.to_string()).filter(|host| !host.is_empty())
+}
+
+/// File names of the pages served for a directory, like `products/index.html` for `products/`.
+const INDEX_FILES: &[&str] = &["index.html", "index.htm"];
+
+/// The crawled index page of the directory `link` if it isn't a crawled page itself,
+/// e.g. `products/index.html` for `products`, which links to `products/` are normalized to.
+fn index_page(link: &str, pages: &HashMap<String, Vec<String>>) -> Option<String> {
+    let directory = link.trim_end_matches('/');
+    INDEX_FILES
+        .iter()
+        .map(|index| format!("{}/{}", directory, index))
+        .find(|page| pages.contains_key(page))
+}
+
+/// Get the raw links of `page`, parsing `content` as Markdown or HTML depending on the page name.
+pub fn get_raw_links(page: &str, content: &str) -> Vec<String> {
+    raw_links(page, content).map(Cow::into_owned).collect()
+}
+
+/// Like `get_raw_links`, but lazily and borrowing from `content` where possible.
+pub fn raw_links<'a>(page: &str, content: &'a str) -> Box<dyn Iterator<Item = Cow<'a, str>> + 'a> {
+    Box::new(page_links(page, content).map(|link| link.href))
+}
+
+/// Like `raw_links`, with the attributes of each link.
+/// Uses the built-in extractors, see `Config::extractors` for others.
+pub fn page_links<'a>(page: &str, content: &'a str) -> Box<dyn Iterator<Item = Link<'a>> + 'a> {
+    if markdown::is_markdown(page) {
+        Box::new(extract::Markdown.extract(content, page).into_iter())
+    } else {
+        Box::new(anchors(content))
+    }
+}
+
+/// `rel` values asking search engines not to follow a link and pass on ranking.
+pub const UNFOLLOWED_RELS: &[&str] = &["nofollow", "sponsored", "ugc"];
+
+/// A link found in a page, before normalization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link<'a> {
+    pub href: Cow<'a, str>,
+    /// The `rel` attribute, empty if there is none.
+    pub rel: &'a str,
+    /// What is inside the link, for html with tags and entities, see `text`.
+    pub text: Cow<'a, str>,
+}
+
+impl Link<'_> {
+    /// The text of the link without tags, with whitespace collapsed and common entities decoded.
+    pub fn text(&self) -> String {
+        let text = HTML_TAG.replace_all(&self.text, " ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        decode_entities(&text)
+    }
+
+    /// The values of the `rel` attribute in `UNFOLLOWED_RELS`, lowercase and in order.
+    /// Empty if the link is followed.
+    pub fn unfollowed_rels(&self) -> Vec<String> {
+        self.rel
+            .split_whitespace()
+            .map(str::to_ascii_lowercase)
+            .filter(|rel| UNFOLLOWED_RELS.contains(&rel.as_str()))
+            .collect()
+    }
+}
+
+/// Run the whole extraction pipeline on the `content` of `page`, giving the names of the linked pages.
+pub fn extract_links(page: &str, content: &str, config: &Config) -> Vec<String> {
+    config
+        .extractors
+        .links(page, content)
+        .into_iter()
+        .filter_map(|link| normalize_link(page, &link.href, config).ok())
+        .collect()
+}
+
+/// Why a link found in a page does not become an edge of the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// The link is a template placeholder, `href=""` or `href="#"`.
+    Placeholder,
+    /// The link only has a fragment, pointing into the same page.
+    InPage,
+    /// The link does not match the domain filter.
+    OffDomain,
+    /// Nothing is left after normalization, e.g. a link to the homepage.
+    Empty,
+    /// The normalized link still has a scheme like `mailto:`.
+    Scheme,
+}
+
+impl std::fmt::Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            DropReason::Placeholder => "placeholder",
+            DropReason::InPage => "in-page",
+            DropReason::OffDomain => "off-domain",
+            DropReason::Empty => "empty",
+            DropReason::Scheme => "scheme-rejected",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Name the page an absolute or root-relative `url` points to, e.g. from a sitemap or log.
+/// The bare domain names `root`. `None` if the url is not a page of the site.
+pub fn page_name(url: &str, root: &str, config: &Config) -> Option<String> {
+    match normalize_link(root, url, config) {
+        Ok(page) => Some(page),
+        // The homepage normalizes to an empty name.
+        Err(DropReason::Empty) => Some(root.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Turn a raw `url` found on `page` into the name of the linked page, or tell why it is dropped.
+pub fn normalize_link(page: &str, url: &str, config: &Config) -> Result<String, DropReason> {
+    let url = url.trim();
+    if url.is_empty() || url == "#" {
+        return Err(DropReason::Placeholder);
+    }
+    if url.starts_with('#') && !config.track_fragments {
+        return Err(DropReason::InPage);
+    }
+
+    let url = config.absolute(page, url);
+    if !config.filter.is_match(&url) {
+        return Err(DropReason::OffDomain);
+    }
+    let (url, fragment) = split_fragment(&url);
+
+    let link = filter_prefix(url, &config.prefix);
+    // Normalize the path on its own, so `page/?ref=x` loses its slash too.
+    let (path, query) = split_query(&link);
+    let mut path = match config.percent_mode {
+        Some(mode) => normalize_percent_encoding(path, mode),
+        None => path.to_string(),
+    };
+    if config.collapse_index {
+        path = remove_index_file(path);
+    }
+    if !config.keep_trailing_slash {
+        path = remove_trailing_slash(path);
+    }
+    let link = match query {
+        Some(query) if !config.strip_query => format!("{}?{}", path, query),
+        _ => path,
+    };
+
+    if link.is_empty() {
+        Err(DropReason::Empty)
+    } else if !is_crawling_leftover(&link) {
+        Err(DropReason::Scheme)
+    } else {
+        match fragment {
+            Some(fragment) if config.track_fragments && !fragment.is_empty() => {
+                Ok(format!("{}#{}", link, fragment))
+            }
+            _ => Ok(link),
+        }
+    }
+}
+
+/// Split `url` into the part before the first `?` and the query after it, if any.
+pub fn split_query(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('?') {
+        Some((url, query)) => (url, Some(query)),
+        None => (url, None),
+    }
+}
+
+/// Split `url` into the part before the first `#` and the fragment after it, if any.
+pub fn split_fragment(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (url, None),
+    }
+}
+
+/// Run the extraction pipeline on the `content` of `page`,
+/// pairing each raw link with its normalization result.
+pub fn explain_links(
+    page: &str,
+    content: &str,
+    config: &Config,
+) -> Vec<(String, Result<String, DropReason>)> {
+    config
+        .extractors
+        .links(page, content)
+        .into_iter()
+        .map(|link| {
+            let result = normalize_link(page, &link.href, config);
+            (link.href.into_owned(), result)
+        })
+        .collect()
+}
+
+/// Collects crawled (page name, html) pairs, in memory or in `spill::SpilledPages`.
+pub trait PageSink {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error>;
+
+    /// Number of pages pushed so far.
+    fn page_count(&self) -> usize;
+}
+
+impl PageSink for Vec<(String, String)> {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        self.push((name, content));
+        Ok(())
+    }
+
+    fn page_count(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Passes pages on to another `PageSink`, noting the names pushed more than once. Later pages
+/// replace earlier ones of the same name in the graph, so these lose a page.
+pub struct NameCheck<'a> {
+    sink: &'a mut dyn PageSink,
+    seen: HashSet<String>,
+    duplicates: BTreeSet<String>,
+}
+
+impl<'a> NameCheck<'a> {
+    pub fn new(sink: &'a mut dyn PageSink) -> Self {
+        Self {
+            sink,
+            seen: HashSet::new(),
+            duplicates: BTreeSet::new(),
+        }
+    }
+
+    /// The names pushed more than once so far, sorted.
+    pub fn duplicates(&self) -> &BTreeSet<String> {
+        &self.duplicates
+    }
+}
+
+impl PageSink for NameCheck<'_> {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        if !self.seen.insert(name.clone()) {
+            self.duplicates.insert(name.clone());
+        }
+        self.sink.push_page(name, content)
+    }
+
+    fn page_count(&self) -> usize {
+        self.sink.page_count()
+    }
+}
+
+/// Read all files in `directory` and its subdirectories as (page name, html) pairs, pushing to
+/// `pages` as each file is done. Pages are named by their path relative to `directory`, like
+/// `blog/index.html`. Hidden subdirectories like `.git` are skipped.
+/// The pages read so far stay usable if this future is dropped halfway, e.g. by a timeout.
+pub async fn read_pages(directory: &Path, pages: &mut dyn PageSink) -> Result<(), anyhow::Error> {
+    read_pages_with_modified(directory, pages, &mut HashMap::new()).await
+}
+
+/// Like `read_pages`, also recording the last-modified time of each file in `modified`,
+/// as far as the platform provides it.
+pub async fn read_pages_with_modified(
+    directory: &Path,
+    pages: &mut dyn PageSink,
+    modified: &mut HashMap<String, SystemTime>,
+) -> Result<(), anyhow::Error> {
+    for path in page_files(directory).await? {
+        read_page(directory, &path, pages, modified).await?;
+    }
+    Ok(())
+}
+
+/// The paths of the files in `directory` and its subdirectories, except hidden subdirectories.
+async fn page_files(directory: &Path) -> Result<Vec<std::path::PathBuf>, anyhow::Error> {
+    let mut files = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+    while let Some(current) = directories.pop() {
+        let mut entries = tokio::fs::read_dir(&current)
+            .await
+            .context(format!("Could not read {}", current.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    directories.push(path);
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Like `read_pages_with_modified`, but only reading `size` of the files in `directory`, picked
+/// at random by `seed`. Returns how many files there are in total.
+pub async fn read_sample(
+    directory: &Path,
+    size: usize,
+    seed: u64,
+    pages: &mut dyn PageSink,
+    modified: &mut HashMap<String, SystemTime>,
+) -> Result<usize, anyhow::Error> {
+    let mut paths = page_files(directory).await?;
+    // The directory order is arbitrary, the same seed should pick the same files anyway.
+    paths.sort_unstable();
+
+    let total = paths.len();
+    for path in sample(paths, size, seed) {
+        read_page(directory, &path, pages, modified).await?;
+    }
+    Ok(total)
+}
+
+/// Read the file at `path` into `pages`, named by its path relative to `directory`,
+/// and its last-modified time into `modified`.
+#[tracing::instrument(name = "read", skip_all, fields(file = %path.display()))]
+async fn read_page(
+    directory: &Path,
+    path: &Path,
+    pages: &mut dyn PageSink,
+    modified: &mut HashMap<String, SystemTime>,
+) -> Result<(), anyhow::Error> {
+    let file = tokio::fs::read_to_string(path)
+        .await
+        .context(format!("Could not read {}", path.display()))?;
+
+    let relative = path.strip_prefix(directory).unwrap_or(path);
+    let key = node_name(&relative.to_string_lossy());
+
+    if let Ok(time) = tokio::fs::metadata(path).await.and_then(|m| m.modified()) {
+        modified.insert(key.clone(), time);
+    }
+    pages.push_page(key, file)
+}
+
+/// Pick `size` of the `items` at random, the same ones for the same `seed`, keeping their order.
+/// All items are kept if there are no more than `size`.
+pub fn sample<T>(items: Vec<T>, size: usize, seed: u64) -> Vec<T> {
+    if size >= items.len() {
+        return items;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut picked = rand::seq::index::sample(&mut rng, items.len(), size).into_vec();
+    picked.sort_unstable();
+
+    let mut picked = picked.into_iter().peekable();
+    items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picked.next_if_eq(i).is_some())
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Name the page at the relative file `path`, using slashes like URLs on every platform.
+pub fn node_name(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Find orphans in the given `graph`, i.e. pages not reachable from `root`.
+pub fn find_orphans<'a>(graph: &'a GraphMap<&str, &str, Directed>, root: &str) -> HashSet<&'a str> {
+    find_unreachable(graph, &[root], Reachability::Forward)
+}
+
+/// Which way links are followed when checking reachability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reachability {
+    /// Pages which can't be reached from a root.
+    Forward,
+    /// Pages from which no root can be reached.
+    Reverse,
+    /// Pages failing either of the above.
+    Both,
+}
+
+impl Reachability {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &["forward", "reverse", "both"];
+}
+
+impl FromStr for Reachability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "forward" => Ok(Reachability::Forward),
+            "reverse" => Ok(Reachability::Reverse),
+            "both" => Ok(Reachability::Both),
+            other => Err(format!(
+                "unknown reachability {}, expected one of {}",
+                other,
+                Reachability::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Find the pages of `graph` which are unreachable from any of the `roots`,
+/// following links in the given `direction`.
+pub fn find_unreachable<'a>(
+    graph: &'a GraphMap<&str, &str, Directed>,
+    roots: &[&str],
+    direction: Reachability,
+) -> HashSet<&'a str> {
+    let mut forward: HashSet<&'a str> = graph.nodes().collect();
+    let mut reverse = forward.clone();
+
+    if direction != Reachability::Reverse {
+        let mut dfs = Dfs::empty(graph);
+        for root in roots {
+            dfs.move_to(root);
+            while let Some(v) = dfs.next(graph) {
+                forward.remove(v);
+            }
+        }
+    }
+    if direction != Reachability::Forward {
+        let mut dfs = Dfs::empty(Reversed(graph));
+        for root in roots {
+            dfs.move_to(root);
+            while let Some(v) = dfs.next(Reversed(graph)) {
+                reverse.remove(v);
+            }
+        }
+    }
+
+    match direction {
+        Reachability::Forward => forward,
+        Reachability::Reverse => reverse,
+        Reachability::Both => forward.union(&reverse).copied().collect(),
+    }
+}
+
+/// Split a newline-separated `list` of root pages into those in `graph` and those missing.
+/// Blank lines are skipped.
+pub fn split_roots<'a>(
+    list: &'a str,
+    graph: &GraphMap<&str, &str, Directed>,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    list.lines()
+        .map(str::trim)
+        .filter(|root| !root.is_empty())
+        .partition(|root| graph.contains_node(root))
+}
+
+/// The pages of `graph` named `name` when ignoring case, sorted. Just `name` if it is a page
+/// itself, several if pages differ from `name` and each other only in case.
+pub fn find_pages_ignoring_case<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    name: &str,
+) -> Vec<&'a str> {
+    if let Some(page) = graph.nodes().find(|&page| page == name) {
+        return vec![page];
+    }
+    let name = name.to_lowercase();
+    let mut pages: Vec<_> = graph
+        .nodes()
+        .filter(|page| page.to_lowercase() == name)
+        .collect();
+    pages.sort_unstable();
+    pages
+}
+
+/// Page names which are conventionally used for the homepage.
+const ROOT_NAMES: &[&str] = &["index", "index.html", "home", "start"];
+
+/// Guess which page of `graph` is the homepage.
+/// Candidates are the pages with a conventional homepage name or, if there are none, the pages
+/// with the fewest path segments. Of those, the one with the most inbound links wins.
+pub fn guess_root<'a>(graph: &GraphMap<&'a str, &'a str, Directed>) -> Option<&'a str> {
+    let segments = |page: &str| page.matches('/').count();
+
+    let mut candidates: Vec<_> = graph.nodes().filter(|n| ROOT_NAMES.contains(n)).collect();
+    if candidates.is_empty() {
+        let fewest = graph.nodes().map(segments).min()?;
+        candidates = graph.nodes().filter(|&n| segments(n) == fewest).collect();
+    }
+
+    candidates.into_iter().max_by(|a, b| {
+        let indegree = |page| graph.neighbors_directed(page, Direction::Incoming).count();
+        indegree(a).cmp(&indegree(b)).then_with(|| b.cmp(a))
+    })
+}
+
+/// Find the index pages of top-level directories, like `blog/index.html` or `shop/index`,
+/// the landing pages of the sections of a site. Sorted.
+pub fn find_section_roots<'a>(graph: &GraphMap<&'a str, &'a str, Directed>) -> Vec<&'a str> {
+    let mut roots: Vec<_> = graph
+        .nodes()
+        .filter(|page| match page.split_once('/') {
+            Some((directory, file)) => {
+                !directory.is_empty()
+                    && !file.contains('/')
+                    && (file == "index" || file.starts_with("index."))
+            }
+            None => false,
+        })
+        .collect();
+    roots.sort_unstable();
+    roots
+}
+
+/// The page furthest away from the root, measured in clicks along shortest paths.
+#[derive(Debug, PartialEq)]
+pub struct DeepestPage<'a> {
+    /// Number of clicks from the root, i.e. the eccentricity of the root.
+    pub depth: usize,
+    /// A shortest path from the root to the deepest page, both included.
+    pub path: Vec<&'a str>,
+}
+
+/// Find the page with the largest BFS distance from `root`.
+/// Ties are broken by page name so the result is stable. Returns `None` if `root` is not in the `graph`.
+pub fn deepest_page<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &'a str,
+) -> Option<DeepestPage<'a>> {
+    if !graph.contains_node(root) {
+        return None;
+    }
+
+    let visited = shortest_paths(graph, root);
+
+    let (&deepest, &(depth, _)) = visited
+        .iter()
+        .max_by(|(a, (da, _)), (b, (db, _))| da.cmp(db).then_with(|| b.cmp(a)))?;
+
+    let mut path = vec![deepest];
+    while let Some(&(_, Some(previous))) = visited.get(path.last().unwrap()) {
+        path.push(previous);
+    }
+    path.reverse();
+
+    Some(DeepestPage { depth, path })
+}
+
+/// Map each page reachable from `root` to its BFS distance from `root`.
+pub fn depths<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &'a str,
+) -> HashMap<&'a str, usize> {
+    shortest_paths(graph, root)
+        .into_iter()
+        .map(|(page, (depth, _))| (page, depth))
+        .collect()
+}
+
+/// Map each page reachable from `root` to (distance from root, predecessor on a shortest path).
+fn shortest_paths<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &'a str,
+) -> HashMap<&'a str, (usize, Option<&'a str>)> {
+    let mut visited = HashMap::new();
+    if !graph.contains_node(root) {
+        return visited;
+    }
+    visited.insert(root, (0, None));
+
+    let mut queue = VecDeque::from(vec![root]);
+    while let Some(node) = queue.pop_front() {
+        let depth = visited[node].0;
+        for next in graph.neighbors(node) {
+            if !visited.contains_key(next) {
+                visited.insert(next, (depth + 1, Some(node)));
+                queue.push_back(next);
+            }
+        }
+    }
+    visited
+}
+
+pub fn make_page_graph(data: &HashMap<String, Vec<String>>) -> GraphMap<&str, &str, Directed> {
+    let mut graph = petgraph::graphmap::GraphMap::<&str, &str, Directed>::new();
+
+    for (k, values) in data {
+        graph.add_node(k.as_str());
+        for value in values {
+            graph.add_node(value.as_str());
+            graph.add_edge(k.as_str(), value.as_str(), "links");
+        }
+    }
+    graph
+}
+
+/// Make a new vec which only contains the Strings matching the regex.
+pub fn filter_regex(items: &[String], regex: &Regex) -> Vec<String> {
+    filter_regex_iter(items, regex).cloned().collect()
+}
+
+/// Lazily keep the `items` matching the regex, without copying them.
+pub fn filter_regex_iter<'r, I>(items: I, regex: &'r Regex) -> impl Iterator<Item = I::Item> + 'r
+where
+    I: IntoIterator,
+    I::IntoIter: 'r,
+    I::Item: AsRef<str>,
+{
+    items
+        .into_iter()
+        .filter(move |s| regex.is_match(s.as_ref()))
+}
+
+/// Replace with empty string all matches of `regex` in `text`.
+pub fn filter_prefix(text: &str, regex: &Regex) -> String {
+    regex.replace(text, "").to_string()
+}
+
+/// How `normalize_percent_encoding` spells paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentMode {
+    /// Decode everything but `/`, `?`, `#`, `%` and control characters, as file names are.
+    Decode,
+    /// Encode everything but printable ASCII, as browsers send it.
+    Encode,
+}
+
+impl PercentMode {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &["decode", "encode"];
+}
+
+impl FromStr for PercentMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decode" => Ok(PercentMode::Decode),
+            "encode" => Ok(PercentMode::Encode),
+            other => Err(format!(
+                "unknown percent mode {}, expected one of {}",
+                other,
+                PercentMode::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Spell the percent-encoding of `path` the same way for every equivalent path, in upper case
+/// where it is kept. A `%` not followed by two hex digits stays as it is when decoding, and is
+/// encoded as `%25` when encoding. If decoding gives invalid UTF-8, the path is encoded instead.
+pub fn normalize_percent_encoding(path: &str, mode: PercentMode) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut encoded = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = match bytes[i] {
+            b'%' => bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        let (byte, width) = match escape {
+            Some(byte) => (byte, 3),
+            None => (bytes[i], 1),
+        };
+        let keep_escaped = matches!(byte, b'/' | b'?' | b'#' | b'%') || byte.is_ascii_control();
+        if escape.is_some() && keep_escaped {
+            decoded.extend_from_slice(format!("%{:02X}", byte).as_bytes());
+        } else {
+            decoded.push(byte);
+        }
+        if (escape.is_some() && keep_escaped) || byte == b'%' || !byte.is_ascii_graphic() {
+            encoded.push_str(&format!("%{:02X}", byte));
+        } else {
+            encoded.push(byte as char);
+        }
+        i += width;
+    }
+    match mode {
+        PercentMode::Decode => String::from_utf8(decoded).unwrap_or(encoded),
+        PercentMode::Encode => encoded,
+    }
+}
+
+/// Remove the trailing slash of `text`, if applicable.
+pub fn remove_trailing_slash(mut text: String) -> String {
+    if text.ends_with('/') {
+        text.pop();
+    }
+    text
+}
+
+/// Remove a trailing `index.html` or `index.htm` of `text`, keeping the slash before it.
+pub fn remove_index_file(mut text: String) -> String {
+    for index in INDEX_FILES {
+        if text == *index || text.ends_with(&format!("/{}", index)) {
+            text.truncate(text.len() - index.len());
+            break;
+        }
+    }
+    text
+}
+
+/// Resolve the relative `link` against the directory of `page`, handling `.` and `..` segments.
+/// A link starting with `?` or `#` points into `page` itself.
+pub fn resolve_relative(page: &str, link: &str) -> String {
+    if link.starts_with('?') || link.starts_with('#') {
+        return format!("{}{}", page, link);
+    }
+
+    let mut segments: Vec<&str> = page.split('/').collect();
+    // The last segment is the page itself, not a directory.
+    segments.pop();
+
+    for segment in link.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Checks if `text` is empty or contains a ':'.
+/// Call this function after filtering out any other http://, mailto:// or text with trailing slashes.
+pub fn is_crawling_leftover(text: &str) -> bool {
+    if text.is_empty() {
+        return false;
+    }
+    if text.contains(':') {
+        return false;
+    }
+    true
+}
+
+/// Get the text of the `<title>` element of the given html, with html entities decoded and
+/// surrounding whitespace removed.
+pub fn get_title_from(text: &str) -> Option<String> {
+    TITLE
+        .captures(text)
+        .map(|c| decode_entities(&c[1]).trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Make a vec with the links from the given html.
+pub fn get_urls_from(text: &str) -> Vec<String> {
+    urls(text).map(str::to_string).collect()
+}
+
+/// Lazily find the links in the given html, borrowing from it.
+pub fn urls(text: &str) -> impl Iterator<Item = &str> {
+    URL.captures_iter(text)
+        .map(|c| c.get(1).or_else(|| c.get(2)).unwrap().as_str())
+}
+
+/// Lazily find the links in the given html along with their `rel` and content.
+pub fn anchors(text: &str) -> impl Iterator<Item = Link<'_>> {
+    URL.captures_iter(text).map(move |c| {
+        let tag = c.get(0).unwrap();
+        // Up to the closing tag, or the next link if it isn't closed.
+        let rest = &text[tag.end()..];
+        let inner = &rest[..ANCHOR_BOUNDARY.find(rest).map_or(rest.len(), |m| m.start())];
+        Link {
+            href: Cow::Borrowed(c.get(1).or_else(|| c.get(2)).unwrap().as_str()),
+            rel: resources::rel(tag.as_str()).unwrap_or(""),
+            text: Cow::Borrowed(inner),
+        }
+    })
+}
+
+/// Replace numeric and the most common named html entities with their characters.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let text = NUMERIC_ENTITY.replace_all(text, |c: &regex::Captures| {
+        let code = match (c.get(1), c.get(2)) {
+            (Some(decimal), _) => decimal.as_str().parse().ok(),
+            (_, Some(hex)) => u32::from_str_radix(hex.as_str(), 16).ok(),
+            _ => None,
+        };
+        code.and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+            .to_string()
+    });
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Download the pages at base_url/{urls}.
+pub async fn get_pages(base_url: &str, urls: &[&str]) -> Result<(), anyhow::Error> {
+    for url in urls {
+        let fname = url.to_string();
+        let url = format!("{}{}", base_url, url);
+        let response = reqwest::get(url).await?;
+
+        let mut dest = { File::create(fname)? };
+
+        let content = response.text().await?;
+        copy(&mut content.as_bytes(), &mut dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use petgraph::dot::{self, Dot};
+
+    #[test]
+    fn extracts_from_huge_malformed_tags() {
+        // A 4 MB `<a` tag that is never closed, running into a proper link.
+        let html = format!(
+            "<a {}<a href=\"/ok\">ok</a> <a href=\"{}",
+            "href=broken ".repeat(350_000),
+            "x".repeat(1_000_000)
+        );
+        let start = std::time::Instant::now();
+        let links: Vec<_> = raw_links("index", &html).collect();
+        assert_eq!(links, vec!["/ok"]);
+        // Quadratic matching would take hours, linear takes a fraction of a second in release
+        // builds and a few seconds in debug builds.
+        assert!(start.elapsed() < std::time::Duration::from_secs(30));
+    }
+
+    #[test]
+    fn regex_matches_url() {
+        let url =
+            r###"<a href='www.traplinked.com'>, some other text, <a  href =   "www.chip.de">"###;
+        assert_eq!(
+            get_urls_from(url),
+            vec!["www.traplinked.com", "www.chip.de"]
+        );
+    }
+
+    #[test]
+    fn malformed_urls() {
+        let url = r###"<a href='www.www.www'> <a>, <a href=www>"###;
+        assert_eq!(get_urls_from(url), vec!["www.www.www"]);
+    }
+
+    #[test]
+    fn gets_title() {
+        let html = "<head><TITLE>\n  Team - traplinked </TITLE></head>";
+        assert_eq!(get_title_from(html), Some("Team - traplinked".to_string()));
+        assert_eq!(get_title_from("<title></title>"), None);
+        assert_eq!(
+            get_title_from("<title>Ideologie &amp; Konzept &#039;21 &quot;neu&quot;</title>"),
+            Some("Ideologie & Konzept '21 \"neu\"".to_string())
+        );
+        assert_eq!(get_title_from("no title"), None);
+    }
+
+    #[test]
+    fn filter_prefixes() {
+        assert_filter("https://www.traplinked.com/hello", "hello");
+        assert_filter("http://www.traplinked.com/thing", "thing");
+        assert_filter("http://www.traplinked.com/tag/this", "tag/this");
+        assert_filter("http://www.traplinked.com/author/who", "author/who");
+    }
+
+    fn assert_filter(text: &str, desired: &str) {
+        let actual = filter_prefix(text, &TRAPL_PREFIXES);
+        assert_eq!(desired, actual);
+    }
+
+    #[test]
+    fn filters_regexes() {
+        let items = vec![
+            "hello@".to_string(),
+            "hel!lo".to_string(),
+            "hello".to_string(),
+        ];
+        let results = filter_regex(&items, &Regex::new(r".*@.*").unwrap());
+        assert_eq!(results, vec!["hello@".to_string()]);
+    }
+
+    #[test]
+    fn chains_iterators() {
+        let html = r#"<a href="https://www.traplinked.com/a"> <a href="https://example.com/b">"#;
+        let links: Vec<_> = filter_regex_iter(urls(html), &FILTER_TRAPL_URLS)
+            .map(|url| filter_prefix(url, &TRAPL_PREFIXES))
+            .collect();
+        assert_eq!(links, vec!["a"]);
+    }
+
+    #[test]
+    fn removes_trailing_slash() {
+        assert_eq!(remove_trailing_slash("test/".to_string()), "test");
+        assert_eq!(remove_trailing_slash("test".to_string()), "test");
+        assert_eq!(remove_trailing_slash("/".to_string()), "");
+        assert_eq!(remove_trailing_slash("t/e/s/t/".to_string()), "t/e/s/t");
+    }
+
+    #[test]
+    fn removes_index_file() {
+        assert_eq!(remove_index_file("page/index.html".to_string()), "page/");
+        assert_eq!(remove_index_file("page/index.htm".to_string()), "page/");
+        assert_eq!(remove_index_file("index.html".to_string()), "");
+        assert_eq!(
+            remove_index_file("page/myindex.html".to_string()),
+            "page/myindex.html"
+        );
+        assert_eq!(remove_index_file("page".to_string()), "page");
+    }
+
+    fn links_with(keep_trailing_slash: bool, collapse_index: bool) -> Vec<String> {
+        let html = r#"<a href="https://www.traplinked.com/page">
+            <a href="https://www.traplinked.com/page/">
+            <a href="https://www.traplinked.com/page/index.html">"#;
+        let config = Config {
+            keep_trailing_slash,
+            collapse_index,
+            ..Config::default()
+        };
+        extract_links("index", html, &config)
+    }
+
+    #[test]
+    fn normalizes_trailing_slash_and_index() {
+        assert_eq!(
+            links_with(false, false),
+            vec!["page", "page", "page/index.html"]
+        );
+        assert_eq!(links_with(false, true), vec!["page", "page", "page"]);
+        assert_eq!(
+            links_with(true, false),
+            vec!["page", "page/", "page/index.html"]
+        );
+        assert_eq!(links_with(true, true), vec!["page", "page/", "page/"]);
+    }
+
+    #[test]
+    fn explains_links() {
+        let html = r#"<a href="https://www.traplinked.com/team/">
+            <a href="https://www.chip.de">
+            <a href="https://www.traplinked.com/">
+            <a href="mailto:info@traplinked.com">"#;
+
+        assert_eq!(
+            explain_links("index", html, &Config::default()),
+            vec![
+                (
+                    "https://www.traplinked.com/team/".to_string(),
+                    Ok("team".to_string())
+                ),
+                (
+                    "https://www.chip.de".to_string(),
+                    Err(DropReason::OffDomain)
+                ),
+                (
+                    "https://www.traplinked.com/".to_string(),
+                    Err(DropReason::Empty)
+                ),
+                (
+                    "mailto:info@traplinked.com".to_string(),
+                    Err(DropReason::Scheme)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_same_domain_url_forms() {
+        let html = r#"<a href="https://www.traplinked.com/team">
+            <a href="//www.traplinked.com/team">
+            <a href="/team">
+            <a href="//www.chip.de/team">"#;
+        assert_eq!(
+            extract_links("index", html, &Config::default()),
+            vec!["team", "team", "team"]
+        );
+
+        let config = Config::for_domain("docs.example.com");
+        let html = r#"<a href="https://docs.example.com/guide/">
+            <a href="//docs.example.com/guide">
+            <a href="/guide">
+            <a href="https://docs.example.com.evil.com/guide">"#;
+        assert_eq!(
+            extract_links("index", html, &config),
+            vec!["guide", "guide", "guide"]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_links() {
+        assert_eq!(resolve_relative("index", "team"), "team");
+        assert_eq!(
+            resolve_relative("docs/intro.md", "setup.md"),
+            "docs/setup.md"
+        );
+        assert_eq!(resolve_relative("docs/a/b.md", "../c.md"), "docs/c.md");
+        assert_eq!(resolve_relative("docs/a.md", "./b/c.md"), "docs/b/c.md");
+        assert_eq!(resolve_relative("a.md", "../../b.md"), "b.md");
+        assert_eq!(resolve_relative("blog", "?page=2"), "blog?page=2");
+    }
+
+    #[test]
+    fn extracts_markdown_links_like_html_links() {
+        let markdown = "[Setup](setup.md), [FAQ](/faq/) and [Chip](https://www.chip.de)";
+        let html = r#"<a href="setup.md"> <a href="/faq/"> <a href="https://www.chip.de">"#;
+
+        let config = Config::default();
+        assert_eq!(
+            extract_links("docs/intro.md", markdown, &config),
+            vec!["docs/setup.md", "faq"]
+        );
+        assert_eq!(
+            extract_links("docs/intro.html", html, &config),
+            vec!["docs/setup.md", "faq"]
+        );
+    }
+
+    #[test]
+    fn maps_fragments_to_nodes() {
+        let html = r#"<a href="https://www.traplinked.com/faq/#shipping">
+            <a href="/faq#returns">
+            <a href="https://www.traplinked.com/team">"#;
+
+        assert_eq!(
+            extract_links("index", html, &Config::default()),
+            vec!["faq", "faq", "team"]
+        );
+
+        let config = Config {
+            track_fragments: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            extract_links("index", html, &config),
+            vec!["faq#shipping", "faq#returns", "team"]
+        );
+
+        let page_graph =
+            build_graph_from_pages(vec![("index".to_string(), html.to_string())], &config);
+        let graph = page_graph.graph();
+        assert!(graph.contains_edge("index", "faq#shipping"));
+        assert_eq!(graph.edge_weight("faq", "faq#shipping"), Some(&"contains"));
+        assert_eq!(graph.edge_weight("faq", "faq#returns"), Some(&"contains"));
+        assert_eq!(graph.node_count(), 5);
+
+        // A page linking to its own fragment keeps the link.
+        let mut links = HashMap::new();
+        links.insert("faq".to_string(), vec!["faq#shipping".to_string()]);
+        links.insert("index".to_string(), vec!["faq#returns".to_string()]);
+        let graph_of_links = PageGraph::from(links);
+        let graph = graph_of_links.graph();
+        assert_eq!(graph.edge_weight("faq", "faq#shipping"), Some(&"links"));
+        assert_eq!(graph.edge_weight("faq", "faq#returns"), Some(&"contains"));
+    }
+
+    #[test]
+    fn drops_placeholder_links() {
+        let html = r##"<a href="#">menu</a> <a href="" class="button">
+            <a href="#top">up</a> <a href='/team'>"##;
+
+        assert_eq!(
+            explain_links("index", html, &Config::default()),
+            vec![
+                ("#".to_string(), Err(DropReason::Placeholder)),
+                ("".to_string(), Err(DropReason::Placeholder)),
+                ("#top".to_string(), Err(DropReason::InPage)),
+                ("/team".to_string(), Ok("team".to_string())),
+            ]
+        );
+
+        let config = Config {
+            track_fragments: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            extract_links("index", html, &config),
+            vec!["index#top", "team"]
+        );
+
+        let pages = vec![
+            ("index".to_string(), html.to_string()),
+            ("team".to_string(), "<a href='/index'>".to_string()),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        assert_eq!(page_graph.placeholder_links().len(), 1);
+        assert_eq!(page_graph.placeholder_links()["index"], 2);
+        assert_eq!(page_graph.graph().node_count(), 2);
+    }
+
+    #[test]
+    fn reports_links_in_discovery_order() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/b"> <a href="https://example.com"> <a href="/a"> <a href="/b">"#
+                    .to_string(),
+            ),
+            ("a".to_string(), "<a href=\"/index\">".to_string()),
+        ];
+
+        let mut seen = Vec::new();
+        build_graph_from_pages_with(pages, &Config::default(), |page, target, kind| {
+            seen.push((page.to_string(), target.to_string(), kind))
+        });
+
+        let seen: Vec<_> = seen
+            .iter()
+            .map(|(page, target, kind)| (page.as_str(), target.as_str(), *kind))
+            .collect();
+        assert_eq!(
+            seen,
+            vec![
+                ("index", "b", LinkKind::Anchor),
+                ("index", "a", LinkKind::Anchor),
+                ("index", "b", LinkKind::Anchor),
+                ("a", "index", LinkKind::Anchor),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_stripped_fragments() {
+        let html = r#"<a href="/faq#a"> <a href="/faq#b"> <a href="/faq#a"> <a href="/faq">"#;
+        let pages = vec![("index".to_string(), html.to_string())];
+
+        let page_graph = build_graph_from_pages(pages.clone(), &Config::default());
+        assert_eq!(page_graph.graph().node_count(), 2);
+        let fragments: Vec<_> = page_graph.fragments()["faq"].iter().collect();
+        assert_eq!(fragments, vec!["a", "b"]);
+
+        let config = Config {
+            track_fragments: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config);
+        assert!(page_graph.fragments().is_empty());
+    }
+
+    #[test]
+    fn dumps_and_loads_links() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/a"> <a href="/b"> <a href="/a">"#.to_string(),
+            ),
+            ("a".to_string(), String::new()),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+
+        let json = dump_links(&page_graph).unwrap();
+        assert_eq!(
+            json,
+            "{\n  \"a\": [],\n  \"index\": [\n    \"a\",\n    \"b\",\n    \"a\"\n  ]\n}"
+        );
+        assert_eq!(load_links(&json).unwrap().links(), page_graph.links());
+    }
+
+    #[test]
+    fn finds_broken_anchors() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r##"<a href="/faq#shipping">ok</a> <a href="/faq#returns">broken</a>
+                <a href="#intro">ok</a> <a href="#gone">broken</a> <a href="#top">top</a>
+                <a href="/blog#latest">not crawled</a>
+                <h1 id="intro">Hi</h1>"##
+                    .to_string(),
+            ),
+            (
+                "faq".to_string(),
+                r#"<h2 id="shipping">Shipping</h2>"#.to_string(),
+            ),
+        ];
+        let config = Config {
+            check_anchors: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages.clone(), &config);
+        assert_eq!(
+            page_graph.broken_anchors(),
+            vec![("index", "faq", "returns"), ("index", "index", "gone")]
+        );
+
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        assert!(page_graph.broken_anchors().is_empty());
+    }
+
+    #[test]
+    fn distinguishes_unfollowed_links() {
+        let pages = vec![(
+            "index".to_string(),
+            r#"<a href="/a" rel="nofollow"> <a rel='sponsored noopener' href="/b">
+               <a href="/c" rel="noopener"> <a href="/c" rel="nofollow">"#
+                .to_string(),
+        )];
+        let page_graph = build_graph_from_pages(pages.clone(), &Config::default());
+        assert_eq!(page_graph.links()["index"], vec!["a", "b", "c", "c"]);
+        assert!(!page_graph.is_followed("index", "a"));
+        assert!(!page_graph.is_followed("index", "b"));
+        assert!(page_graph.is_followed("index", "c"));
+        let rels = &page_graph.unfollowed()[&("index".to_string(), "b".to_string())];
+        assert_eq!(rels.iter().collect::<Vec<_>>(), vec!["sponsored"]);
+
+        let config = Config {
+            follow_only: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config);
+        assert_eq!(page_graph.links()["index"], vec!["c"]);
+        assert_eq!(page_graph.unfollowed().len(), 2);
+    }
+
+    #[test]
+    fn links_directories_to_index_pages() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/products/"> <a href="products#top"> <a href="/blog/">"#.to_string(),
+            ),
+            (
+                "products/index.html".to_string(),
+                r#"<a href="/products/index.html">"#.to_string(),
+            ),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+
+        assert_eq!(
+            page_graph.links()["index"],
+            vec!["products/index.html", "products/index.html", "blog"]
+        );
+        assert!(page_graph.fragments().contains_key("products/index.html"));
+        let graph = page_graph.graph();
+        assert!(!graph.contains_node("products"));
+        // Its link to itself only shows once the directory is resolved, and is dropped too.
+        assert!(!graph.contains_edge("products/index.html", "products/index.html"));
+        assert_eq!(page_graph.self_link_pages(), vec!["products/index.html"]);
+    }
+
+    #[test]
+    fn normalizes_percent_encoding() {
+        use PercentMode::*;
+        for path in &[
+            "caf%C3%A9/a%20b",
+            "caf%c3%a9/a b",
+            "café/a%20b",
+            "c%61fé/a b",
+        ] {
+            assert_eq!(normalize_percent_encoding(path, Decode), "café/a b");
+            assert_eq!(normalize_percent_encoding(path, Encode), "caf%C3%A9/a%20b");
+        }
+        // Escaped separators stay escaped, as decoding them would change the path.
+        assert_eq!(normalize_percent_encoding("a%2fb%3F", Decode), "a%2Fb%3F");
+        assert_eq!(normalize_percent_encoding("a%2fb%3F", Encode), "a%2Fb%3F");
+        // Invalid sequences and UTF-8 don't panic.
+        assert_eq!(normalize_percent_encoding("100%", Decode), "100%");
+        assert_eq!(normalize_percent_encoding("100%", Encode), "100%25");
+        assert_eq!(normalize_percent_encoding("%zz%4", Decode), "%zz%4");
+        assert_eq!(normalize_percent_encoding("%zz%4", Encode), "%25zz%254");
+        assert_eq!(normalize_percent_encoding("%ff%C3", Decode), "%FF%C3");
+        assert_eq!(normalize_percent_encoding("é%", Encode), "%C3%A9%25");
+        // `from_str_radix` would take the sign.
+        assert_eq!(normalize_percent_encoding("e%+Fy%-1", Decode), "e%+Fy%-1");
+        assert_eq!(
+            normalize_percent_encoding("e%+Fy%-1", Encode),
+            "e%25+Fy%25-1"
+        );
+    }
+
+    #[test]
+    fn merges_percent_encoded_pages() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/caf%C3%A9"> <a href="/café"> <a href="/caf%c3%a9?x=%41">"#.to_string(),
+            ),
+            ("caf%c3%a9".to_string(), r#"<a href="/">"#.to_string()),
+        ];
+
+        let page_graph = build_graph_from_pages(pages.clone(), &Config::default());
+        assert_eq!(page_graph.graph().node_count(), 5);
+
+        for (mode, name) in &[
+            (PercentMode::Decode, "café"),
+            (PercentMode::Encode, "caf%C3%A9"),
+        ] {
+            let config = Config {
+                percent_mode: Some(*mode),
+                strip_query: true,
+                ..Config::default()
+            };
+            let page_graph = build_graph_from_pages(pages.clone(), &config);
+            let graph = page_graph.graph();
+            let mut nodes: Vec<_> = graph.nodes().collect();
+            nodes.sort_unstable();
+            assert_eq!(nodes, vec![*name, "index"]);
+            assert_eq!(page_graph.links()["index"], vec![*name; 3]);
+        }
+    }
+
+    #[test]
+    fn builds_graph_from_reader() {
+        let html: &[u8] = br#"<title>Home</title> <a href="/a">A</a> <a href="b.html">B</a>"#;
+        let config = Config::for_domain("example.com");
+
+        let page_graph = build_graph_from_reader("index", html, &config).unwrap();
+
+        assert_eq!(page_graph.links()["index"], vec!["a", "b.html"]);
+        assert_eq!(page_graph.title("index"), Some("Home"));
+        let invalid: &[u8] = &[0xff, 0xfe];
+        assert!(build_graph_from_reader("index", invalid, &config).is_err());
+    }
+
+    #[test]
+    fn records_link_text_variants() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/pricing">Pricing</a> <a href="/pricing">Plans</a>
+                <a href="/team">Team</a> <a href="/pricing"><img src="p.png"></a>"#
+                    .to_string(),
+            ),
+            (
+                "blog".to_string(),
+                r#"<a href="/pricing">See  <b>prices</b></a> <a href="/pricing">&#80;ri&#x63;ing</a>
+                <a href="/team">Our team</a>"#
+                    .to_string(),
+            ),
+        ];
+        let config = Config {
+            record_link_texts: true,
+            ..Config::for_domain("example.com")
+        };
+
+        let page_graph = build_graph_from_pages(pages.clone(), &config);
+
+        assert_eq!(
+            page_graph.link_text_variants(3),
+            vec![("pricing", vec!["Plans", "Pricing", "See prices"])]
+        );
+        assert_eq!(page_graph.link_text_variants(2).len(), 2);
+        let page_graph = build_graph_from_pages(pages, &Config::for_domain("example.com"));
+        assert!(page_graph.link_text_variants(1).is_empty());
+    }
+
+    #[test]
+    fn drops_self_links() {
+        let pages = vec![(
+            "blog".to_string(),
+            r##"<a href="?page=2">2</a> <a href="/blog?page=3">3</a> <a href="#top">top</a>
+            <a href="/post">post</a>"##
+                .to_string(),
+        )];
+        let config = Config {
+            strip_query: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages.clone(), &config);
+        assert_eq!(page_graph.links()["blog"], vec!["post"]);
+        assert_eq!(page_graph.dropped_self_links()["blog"], 2);
+        assert_eq!(page_graph.self_link_pages(), vec!["blog"]);
+
+        let config = Config {
+            keep_self_links: true,
+            ..config
+        };
+        let page_graph = build_graph_from_pages(pages, &config);
+        assert_eq!(page_graph.links()["blog"], vec!["blog", "blog", "post"]);
+        assert!(page_graph.dropped_self_links().is_empty());
+        assert_eq!(page_graph.self_link_pages(), vec!["blog"]);
+    }
+
+    #[test]
+    fn keeps_external_hosts_as_leaves() {
+        let page = r#"<a href="/a"> <a href="https://GitHub.com/x"> <a href="http://ex.org?q">
+                      <a href="mailto:hi@ex.org"> <a href="https://github.com/y">"#;
+        let pages = vec![("index".to_string(), page.to_string())];
+        let config = Config {
+            include_external: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages.clone(), &config);
+        assert_eq!(page_graph.links()["index"], vec!["a"]);
+        let hosts: Vec<_> = page_graph.external_links()["index"].iter().collect();
+        assert_eq!(hosts, vec!["ex.org", "github.com"]);
+
+        let external = page_graph.with_external_nodes();
+        assert_eq!(external.links()["index"], vec!["a", "ex.org", "github.com"]);
+        assert!(external.is_external("github.com"));
+        assert!(!external.is_external("a"));
+        assert_eq!(external.graph().node_count(), 4);
+        assert_eq!(page_graph.graph().node_count(), 2);
+
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        assert!(page_graph.external_links().is_empty());
+    }
+
+    #[test]
+    fn respects_meta_robots() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/draft"> <a href="/links"> <a href="/a">"#.to_string(),
+            ),
+            (
+                "draft".to_string(),
+                r#"<meta name="robots" content="noindex"> <a href="/b">"#.to_string(),
+            ),
+            (
+                "links".to_string(),
+                r#"<meta name="robots" content="nofollow"> <a href="/c">"#.to_string(),
+            ),
+        ];
+        let config = Config {
+            respect_meta_robots: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages.clone(), &config);
+
+        assert_eq!(page_graph.links()["index"], vec!["links", "a"]);
+        assert!(page_graph.links()["links"].is_empty());
+        assert!(!page_graph.links().contains_key("draft"));
+        let graph = page_graph.graph();
+        assert_eq!(graph.node_count(), 3);
+        assert!(crate::find_orphans(&graph, "index").is_empty());
+        assert_eq!(page_graph.noindex_pages().len(), 1);
+        assert_eq!(page_graph.nofollow_pages().len(), 1);
+
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        assert_eq!(page_graph.graph().node_count(), 6);
+        assert!(page_graph.noindex_pages().is_empty());
+    }
+
+    #[test]
+    fn merges_all_findings() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r##"<a href="/index">me</a> <a href="/a#top">A</a> <a href="/a">a</a>
+                    <a href="/b" rel="sponsored"> <a href="https://example.com/"> <a href="#">"##
+                    .to_string(),
+            ),
+            ("a".to_string(), r#"<h2 id="intro">"#.to_string()),
+            (
+                "draft".to_string(),
+                r#"<meta name="robots" content="noindex">"#.to_string(),
+            ),
+            (
+                "links".to_string(),
+                r#"<meta name="robots" content="nofollow">"#.to_string(),
+            ),
+        ];
+        let config = Config {
+            follow_only: true,
+            include_external: true,
+            respect_meta_robots: true,
+            check_anchors: true,
+            record_link_texts: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config).with_external_nodes();
+        assert!(!page_graph.unfollowed.is_empty());
+        assert!(!page_graph.external_nodes.is_empty());
+        assert!(!page_graph.noindex.is_empty());
+        assert!(!page_graph.nofollow.is_empty());
+        assert!(!page_graph.anchor_targets.is_empty());
+        assert!(!page_graph.fragment_links.is_empty());
+        assert!(!page_graph.dropped_self_links.is_empty());
+        assert!(!page_graph.link_texts.is_empty());
+
+        let merged = PageGraph::default().merged_with(page_graph.clone());
+        assert_eq!(merged, page_graph);
+        let twice = page_graph.clone().merged_with(page_graph.clone());
+        assert_eq!(twice.dropped_self_links()["index"], 2);
+        assert_eq!(twice.link_texts, page_graph.link_texts);
+    }
+
+    #[test]
+    fn drops_links_by_anchor_text() {
+        let page = r#"<a href="/a">Pricing</a> <a href="/index"><span>Back to</span>
+                      TOP</a> <a href="/privacy">Privacy &amp; Cookies</a> <a href="/b">"#;
+        let texts: Vec<_> = anchors(page).map(|link| link.text()).collect();
+        assert_eq!(
+            texts,
+            vec!["Pricing", "Back to TOP", "Privacy & Cookies", ""]
+        );
+
+        let config = Config {
+            drop_anchor_texts: vec!["back to top".to_string(), "privacy & cookies".to_string()],
+            ..Config::default()
+        };
+        let pages = vec![("blog".to_string(), page.to_string())];
+        let page_graph = build_graph_from_pages(pages, &config);
+        assert_eq!(page_graph.links()["blog"], vec!["a", "b"]);
+    }
+
+    #[test]
+    fn matches_hosts_case_insensitively() {
+        let html =
+            r#"<a href="HTTPS://WWW.Example.COM/Team"> <a href="https://www.example.com/team">"#;
+        assert_eq!(
+            extract_links("index", html, &Config::for_domain("www.example.com")),
+            vec!["Team", "team"]
+        );
+        assert_eq!(
+            extract_links("index", html, &Config::for_domain("WWW.EXAMPLE.COM")),
+            vec!["Team", "team"]
+        );
+
+        let html = r#"<a href="https://www.TrapLinked.com/en/shop">"#;
+        assert_eq!(
+            extract_links("index", html, &Config::default()),
+            vec!["shop"]
+        );
+    }
+
+    #[test]
+    fn rewrites_to_canonical_host() {
+        let config =
+            Config::for_domain("staging.example.com").with_canonical_host("www.example.com");
+        let html = r#"<a href="https://staging.example.com/team"> <a href="https://www.example.com/shop">
+            <a href="/imprint"> <a href="https://other.example.com/team">"#;
+
+        assert_eq!(
+            extract_links("index", html, &config),
+            vec!["team", "shop", "imprint"]
+        );
+        assert_eq!(
+            config.absolute("index", "/imprint"),
+            "https://www.example.com/imprint"
+        );
+    }
+
+    #[test]
+    fn names_nodes_with_slashes() {
+        assert_eq!(node_name(r"blog\2021\post.html"), "blog/2021/post.html");
+        assert_eq!(node_name("blog/post.html"), "blog/post.html");
+        assert_eq!(node_name("index"), "index");
+    }
+
+    #[test]
+    fn normalizes_path_before_query() {
+        let link = |url, config: &Config| normalize_link("index", url, config);
+        let config = Config::default();
+        assert_eq!(link("/page/?ref=x", &config), Ok("page?ref=x".to_string()));
+        assert_eq!(link("/page?ref=x", &config), Ok("page?ref=x".to_string()));
+        assert_eq!(link("/?ref=x", &config), Ok("?ref=x".to_string()));
+
+        let config = Config {
+            strip_query: true,
+            collapse_index: true,
+            ..Config::default()
+        };
+        assert_eq!(link("/page/?ref=x", &config), Ok("page".to_string()));
+        assert_eq!(link("/page/?ref=x#top", &config), Ok("page".to_string()));
+        assert_eq!(
+            link("/page/index.html?ref=x", &config),
+            Ok("page".to_string())
+        );
+        assert_eq!(link("/?ref=x", &config), Err(DropReason::Empty));
+    }
+
+    #[test]
+    fn checks_tag() {
+        assert!(!is_crawling_leftover(""));
+        assert!(!is_crawling_leftover("mailto:lchereti"));
+        assert!(is_crawling_leftover("/author/lchereti"));
+    }
+
+    #[test]
+    fn makes_graph_map() {
+        let mut data = HashMap::new();
+        data.insert("a".to_string(), vec!["b".to_string(), "c".to_string()]);
+        data.insert("b".to_string(), vec!["c".to_string()]);
+        data.insert("c".to_string(), vec![]);
+
+        let graph = make_page_graph(&data);
+
+        println!(
+            "{:?}",
+            Dot::with_config(&graph, &[dot::Config::EdgeNoLabel])
+        );
+
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.node_count(), 3);
+        assert!(graph.contains_node("a"));
+        assert!(graph.contains_node("b"));
+        assert!(graph.contains_node("c"));
+
+        assert!(graph.contains_edge("a", "b"));
+        assert!(graph.contains_edge("a", "c"));
+        assert!(graph.contains_edge("b", "c"));
+    }
+
+    #[test]
+    fn finds_deepest_page() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["c".to_string(), "index".to_string()]);
+        data.insert("b".to_string(), vec!["c".to_string()]);
+        data.insert("c".to_string(), vec!["d".to_string()]);
+        data.insert("orphan".to_string(), vec!["index".to_string()]);
+
+        let graph = make_page_graph(&data);
+
+        let deepest = deepest_page(&graph, "index").unwrap();
+        assert_eq!(deepest.depth, 3);
+        assert_eq!(deepest.path.len(), 4);
+        assert_eq!(deepest.path.first(), Some(&"index"));
+        assert_eq!(deepest.path.last(), Some(&"d"));
+
+        assert_eq!(deepest_page(&graph, "missing"), None);
+    }
+
+    #[test]
+    fn builds_graph_from_pages() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="https://www.traplinked.com/team/">, <a href="https://www.chip.de">"#
+                    .to_string(),
+            ),
+            ("team".to_string(), String::new()),
+        ];
+
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        let graph = page_graph.graph();
+
+        assert_eq!(page_graph.links()["index"], vec!["team"]);
+        assert_eq!(page_graph.title("index"), None);
+        assert_eq!(graph.node_count(), 2);
+        assert!(graph.contains_edge("index", "team"));
+    }
+
+    #[test]
+    fn samples_deterministically() {
+        let files: Vec<_> = (0..100).collect();
+
+        let sampled = sample(files.clone(), 10, 7);
+        assert_eq!(sampled.len(), 10);
+        assert!(sampled.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(sample(files.clone(), 10, 7), sampled);
+        assert_ne!(sample(files.clone(), 10, 8), sampled);
+
+        assert_eq!(sample(files.clone(), 200, 7), files);
+    }
+
+    #[test]
+    fn finds_unreachable_in_each_direction() {
+        let mut data = HashMap::new();
+        data.insert(
+            "index".to_string(),
+            vec!["a".to_string(), "dead-end".to_string()],
+        );
+        data.insert("a".to_string(), vec!["index".to_string()]);
+        data.insert("orphan".to_string(), vec!["a".to_string()]);
+        data.insert("section".to_string(), vec!["b".to_string()]);
+        data.insert("b".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+
+        fn sorted(set: HashSet<&str>) -> Vec<&str> {
+            let mut pages: Vec<_> = set.into_iter().collect();
+            pages.sort_unstable();
+            pages
+        }
+
+        let forward = find_unreachable(&graph, &["index"], Reachability::Forward);
+        assert_eq!(sorted(forward), vec!["b", "orphan", "section"]);
+
+        let reverse = find_unreachable(&graph, &["index"], Reachability::Reverse);
+        assert_eq!(sorted(reverse), vec!["b", "dead-end", "section"]);
+
+        let both = find_unreachable(&graph, &["index"], Reachability::Both);
+        assert_eq!(sorted(both), vec!["b", "dead-end", "orphan", "section"]);
+
+        let forward = find_unreachable(&graph, &["index", "section"], Reachability::Forward);
+        assert_eq!(sorted(forward), vec!["orphan"]);
+    }
+
+    #[test]
+    fn finds_pages_ignoring_case() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["About".to_string()]);
+        data.insert("FAQ".to_string(), vec!["faq".to_string()]);
+        let graph = make_page_graph(&data);
+
+        assert_eq!(find_pages_ignoring_case(&graph, "Index"), vec!["index"]);
+        assert_eq!(find_pages_ignoring_case(&graph, "ABOUT"), vec!["About"]);
+        assert_eq!(find_pages_ignoring_case(&graph, "faq"), vec!["faq"]);
+        assert_eq!(find_pages_ignoring_case(&graph, "Faq"), vec!["FAQ", "faq"]);
+        assert!(find_pages_ignoring_case(&graph, "contact").is_empty());
+    }
+
+    #[test]
+    fn reads_root_list() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["about".to_string()]);
+        data.insert("blog".to_string(), vec!["blog/post".to_string()]);
+        data.insert("shop".to_string(), vec!["shop/cart".to_string()]);
+        data.insert("hidden".to_string(), vec![]);
+        let graph = make_page_graph(&data);
+
+        let (roots, missing) = split_roots(
+            "index
+blog
+
+  shop 
+forum
+",
+            &graph,
+        );
+        assert_eq!(roots, vec!["index", "blog", "shop"]);
+        assert_eq!(missing, vec!["forum"]);
+
+        let orphans = find_unreachable(&graph, &roots, Reachability::Forward);
+        assert_eq!(orphans.into_iter().collect::<Vec<_>>(), vec!["hidden"]);
+    }
+
+    #[test]
+    fn restricts_to_main_component() {
+        let page = |html: &str| html.to_string();
+        let pages = vec![
+            (
+                "index".to_string(),
+                page(r#"<a href="/a">A</a> <a href="/b#x">"#),
+            ),
+            (
+                "a".to_string(),
+                page(r#"<a href="/index">Home</a> <a href="/c">"#),
+            ),
+            ("old".to_string(), page(r#"<a href="/older">Older</a>"#)),
+            ("test".to_string(), page("<title>Test</title>")),
+            ("junk".to_string(), page(r#"<a href="/junk">"#)),
+        ];
+        let config = Config {
+            record_link_texts: true,
+            ..Config::default()
+        };
+        let page_graph = build_graph_from_pages(pages, &config);
+        let graph = page_graph.graph();
+        let components = crate::analysis::find_components(&graph);
+        let sizes: Vec<_> = components.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![4, 2, 1, 1]);
+
+        let main: HashSet<_> = components[0].iter().copied().collect();
+        let restricted = page_graph.restricted_to(&main);
+
+        let mut nodes: Vec<_> = restricted.graph().nodes().collect();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec!["a", "b", "c", "index"]);
+        assert_eq!(restricted.title("test"), None);
+        assert!(restricted.dropped_self_links().is_empty());
+        assert_eq!(restricted.fragments().len(), 1);
+        assert_eq!(restricted.link_text_variants(1).len(), 2);
+    }
+
+    #[test]
+    fn collapses_pagination() {
+        let page = |html: &str| html.to_string();
+        let pages = vec![
+            (
+                "index".to_string(),
+                page(r#"<a href="/blog">Blog</a> <a href="/blog/page/2">"#),
+            ),
+            (
+                "blog".to_string(),
+                page(r#"<title>Blog</title> <a href="/blog/page/2"> <a href="/post-1">"#),
+            ),
+            (
+                "blog/page/2".to_string(),
+                page(r#"<title>Blog 2</title> <a href="/blog/page/3"> <a href="/post-2">"#),
+            ),
+            (
+                "blog/page/3".to_string(),
+                page(r#"<a href="/blog/page/2"> <a href="/post-3">"#),
+            ),
+            ("post-1".to_string(), page(r#"<a href="/blog/page/3">"#)),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        let pagination = Regex::new(r"/page/\d+").unwrap();
+
+        let parents = page_graph.pagination_parents(&pagination);
+        let mut collapsed: Vec<_> = parents.keys().map(String::as_str).collect();
+        collapsed.sort_unstable();
+        assert_eq!(collapsed, vec!["blog/page/2", "blog/page/3"]);
+        assert!(parents.values().all(|parent| parent == "blog"));
+
+        let merged = page_graph.with_pages_merged(&parents);
+        let mut nodes: Vec<_> = merged.graph().nodes().collect();
+        nodes.sort_unstable();
+        assert_eq!(nodes, vec!["blog", "index", "post-1", "post-2", "post-3"]);
+        let mut links = merged.links()["blog"].clone();
+        links.sort_unstable();
+        assert_eq!(links, vec!["post-1", "post-2", "post-3"]);
+        let counts = merged.link_counts();
+        assert_eq!(counts[&("index", "blog")], 2);
+        assert_eq!(counts[&("post-1", "blog")], 1);
+        assert_eq!(merged.title("blog"), Some("Blog"));
+        assert_eq!(merged.dropped_self_links()["blog"], 3);
+    }
+
+    #[test]
+    fn finds_missing_link_targets() {
+        let pages = vec![
+            (
+                "index.html".to_string(),
+                r#"<a href="products/x.html"> <a href="products/x.html"> <a href="blog">
+                <a href="blog/post.html#top"> <a href="https://github.com/">"#
+                    .to_string(),
+            ),
+            (
+                "blog/index.html".to_string(),
+                r#"<a href="post.html">"#.to_string(),
+            ),
+            (
+                "blog/post.html".to_string(),
+                r#"<a href="../about">"#.to_string(),
+            ),
+        ];
+        let config = Config {
+            include_external: true,
+            ..Config::for_domain("example.com")
+        };
+
+        let page_graph = build_graph_from_pages(pages, &config);
+
+        assert_eq!(
+            page_graph.missing_link_targets(),
+            vec![
+                ("blog/post.html", "about"),
+                ("index.html", "products/x.html")
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_section_roots() {
+        let page = |html: &str| html.to_string();
+        let pages = vec![
+            ("index".to_string(), page(r#"<a href="/blog/">"#)),
+            ("blog/index.html".to_string(), page(r#"<a href="/blog/a">"#)),
+            ("blog/a".to_string(), page("")),
+            ("shop/index.md".to_string(), page("[Item](/shop/item)")),
+            ("shop/item".to_string(), page("")),
+            ("shop/old/index.html".to_string(), page("")),
+            ("about/team".to_string(), page("")),
+        ];
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        let graph = page_graph.graph();
+
+        let sections = find_section_roots(&graph);
+        assert_eq!(sections, vec!["blog/index.html", "shop/index.md"]);
+
+        let mut orphans: Vec<_> = find_unreachable(&graph, &["index"], Reachability::Forward)
+            .into_iter()
+            .collect();
+        orphans.sort_unstable();
+        assert_eq!(
+            orphans,
+            vec![
+                "about/team",
+                "shop/index.md",
+                "shop/item",
+                "shop/old/index.html"
+            ]
+        );
+
+        let mut roots = sections;
+        roots.push("index");
+        let mut orphans: Vec<_> = find_unreachable(&graph, &roots, Reachability::Forward)
+            .into_iter()
+            .collect();
+        orphans.sort_unstable();
+        assert_eq!(orphans, vec!["about/team", "shop/old/index.html"]);
+    }
+
+    #[test]
+    fn guesses_root() {
+        let mut data = HashMap::new();
+        data.insert("home".to_string(), vec!["blog/a".to_string()]);
+        data.insert(
+            "blog/a".to_string(),
+            vec!["home".to_string(), "blog".to_string()],
+        );
+        data.insert(
+            "blog".to_string(),
+            vec!["home".to_string(), "blog/a".to_string()],
+        );
+        let graph = make_page_graph(&data);
+        assert_eq!(guess_root(&graph), Some("home"));
+
+        // Without a conventional name, the shallow page with most inbound links wins.
+        let mut data = HashMap::new();
+        data.insert("start-here".to_string(), vec!["about".to_string()]);
+        data.insert("about".to_string(), vec!["start-here".to_string()]);
+        data.insert("blog/a".to_string(), vec!["start-here".to_string()]);
+        let graph = make_page_graph(&data);
+        assert_eq!(guess_root(&graph), Some("start-here"));
+
+        let data = HashMap::new();
+        assert_eq!(guess_root(&make_page_graph(&data)), None);
+    }
+
+    #[tokio::test]
+    async fn reads_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("index"),
+            r#"<a href="https://www.traplinked.com/team/">team</a>"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("team"), "no links here").unwrap();
+
+        let mut pages = Vec::new();
+        read_pages(dir.path(), &mut pages).await.unwrap();
+        pages.sort();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].0, "index");
+        assert_eq!(pages[1], ("team".to_string(), "no links here".to_string()));
+    }
+
+    #[tokio::test]
+    async fn reads_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        for directory in &["blog", "products", ".git"] {
+            std::fs::create_dir(dir.path().join(directory)).unwrap();
+            std::fs::write(dir.path().join(directory).join("index.html"), *directory).unwrap();
+        }
+        std::fs::write(dir.path().join("index.html"), r#"<a href="/blog/">"#).unwrap();
+
+        let mut pages = Vec::new();
+        let mut checked = NameCheck::new(&mut pages);
+        read_pages(dir.path(), &mut checked).await.unwrap();
+        assert!(checked.duplicates().is_empty());
+        pages.sort();
+
+        let names: Vec<_> = pages.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["blog/index.html", "index.html", "products/index.html"]
+        );
+        let page_graph = build_graph_from_pages(pages, &Config::default());
+        assert_eq!(page_graph.graph().node_count(), 3);
+        assert_eq!(page_graph.links()["index.html"], vec!["blog/index.html"]);
+
+        let mut pages = Vec::new();
+        let mut checked = NameCheck::new(&mut pages);
+        for name in &["a/b", "c", "a/b"] {
+            checked.push_page(name.to_string(), String::new()).unwrap();
+        }
+        assert_eq!(checked.page_count(), 3);
+        assert_eq!(checked.duplicates().iter().collect::<Vec<_>>(), vec!["a/b"]);
+    }
+
+    #[tokio::test]
+    async fn reads_modified_times() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("index"), "").unwrap();
+
+        let mut pages = Vec::new();
+        let mut modified = HashMap::new();
+        read_pages_with_modified(dir.path(), &mut pages, &mut modified)
+            .await
+            .unwrap();
+
+        let age = SystemTime::now().duration_since(modified["index"]).unwrap();
+        assert!(age.as_secs() < 60);
+    }
+}
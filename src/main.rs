@@ -1,282 +1,1887 @@
 use anyhow::Context;
 
-use lazy_static::lazy_static;
+use page_graph::analysis::{
+    count_by_rel, find_closed_cycles, find_components, find_content_orphans, find_leaf_pages,
+    find_link_heavy, find_missing_back_links, find_mutual_links, find_orphan_entries,
+    find_under_linked, group_by_section, inbound_by_prefix, remove_allowed_orphans,
+};
+use page_graph::anonymize::{anonymize, hash_name};
+use page_graph::archive::{is_archive, read_archive};
+use page_graph::budget::{Budget, Limited, Stop};
+use page_graph::events::{find_events, to_ndjson};
+use page_graph::format::{self, Format, RenderOptions};
+use page_graph::manifest::Manifest;
+use page_graph::resources::find_mixed_content;
+use page_graph::sitemap::{self, find_buried_pages};
+use page_graph::spill::SpilledPages;
+use page_graph::stats::{compare, comparison_table, Change, GraphStats};
+use page_graph::traffic::{find_unexpected, parse_access_log};
 
-use petgraph::dot::{Config, Dot};
 use petgraph::graphmap::GraphMap;
-use petgraph::*;
+use petgraph::Directed;
 
-use petgraph::visit::Dfs;
 use regex::Regex;
 
+use page_graph::{
+    build_graph_from_reader, deepest_page, depths, dump_links, explain_links,
+    find_pages_ignoring_case, find_section_roots, find_unreachable, guess_root, load_links,
+    read_pages_with_modified, read_sample, sample, split_roots, Config, GraphBuilder, NameCheck,
+    PageGraph, PageSink, PercentMode, Reachability,
+};
+
 use std::collections::{HashMap, HashSet};
-use std::fs::{self, File, OpenOptions};
-use std::io::{copy, Write};
-use std::path::PathBuf;
+use std::fs::{self, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use structopt::StructOpt;
 
-mod urls;
+use tracing::{info_span, Instrument, Subscriber};
+use tracing_chrome::{ChromeLayer, ChromeLayerBuilder, FlushGuard, TraceStyle};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
 
-lazy_static! {
-    static ref URL: Regex =
-        Regex::new(r###"<a[^>]*?href\s*=\s*['|"]([^#\\/].*?)['|"][^>]*?>"###).unwrap();
-}
+#[derive(Debug, Clone, StructOpt)]
+struct Opt {
+    /// Directory with HTML or Markdown (`.md`) files, also in subdirectories, or a `.zip`, `.tar`
+    /// or `.tar.gz` of them.
+    #[structopt(
+        short = "d",
+        long,
+        parse(from_os_str),
+        required_unless_one = &["explain", "manifest", "links-from", "compare", "stdin"]
+    )]
+    directory: Option<PathBuf>,
 
-lazy_static! {
-    static ref TRAPL_PREFIXES: Regex =
-        Regex::new(r###"http[s]?://www.traplinked.com/(en/|nl/)?"###).unwrap();
-}
+    /// JSON file listing sites to process one after the other, each with a `directory` and
+    /// optionally its own `domain`, `root` and `output`. Other options apply to all of them.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["directory", "output", "output-dir"]
+    )]
+    manifest: Option<PathBuf>,
 
-lazy_static! {
-    static ref FILTER_TRAPL_URLS: Regex = Regex::new(r###".*traplinked.*"###).unwrap();
-}
+    /// Build the graph from links written by `--dump-links` instead of reading pages.
+    #[structopt(long, parse(from_os_str), conflicts_with_all = &["directory", "manifest"])]
+    links_from: Option<PathBuf>,
 
-#[derive(Debug, StructOpt)]
-struct Opt {
-    /// Directory with HTML files.
-    #[structopt(short = "d", long, parse(from_os_str))]
-    directory: PathBuf,
+    /// Read a single page from stdin instead of a directory, e.g. `curl https://example.com/ |
+    /// page-graph --stdin --domain example.com`.
+    #[structopt(long, conflicts_with_all = &["directory", "manifest", "links-from"])]
+    stdin: bool,
+
+    /// Name of the page read with `--stdin`.
+    #[structopt(long, default_value = "index")]
+    stdin_name: String,
+
+    /// Write the links found on each page to this JSON file, before they become a graph.
+    #[structopt(long, parse(from_os_str))]
+    dump_links: Option<PathBuf>,
+
+    /// Host of the site, links to other hosts are dropped [default: www.traplinked.com].
+    #[structopt(long)]
+    domain: Option<String>,
+
+    /// Also keep links to this host, merging its pages with the `--domain` ones, and use it in output.
+    #[structopt(long)]
+    canonical_host: Option<String>,
+
+    /// Show how each link in this file is normalized, and why dropped links are dropped.
+    #[structopt(long, parse(from_os_str))]
+    explain: Option<PathBuf>,
+
+    /// Compare the stats of two graphs saved with `--format json`, old then new, as a table with
+    /// the change of each. Made for release notes, more orphans and the like are regressions.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        number_of_values = 2,
+        value_names = &["old", "new"],
+        conflicts_with_all = &["directory", "manifest", "links-from"]
+    )]
+    compare: Vec<PathBuf>,
+
+    /// Print the `--compare` table as JSON.
+    #[structopt(long, requires = "compare")]
+    compare_json: bool,
 
     /// Output file, default to stdout.
     #[structopt(short = "o", long, parse(from_os_str))]
     output: Option<PathBuf>,
 
+    /// Write the graph, `stats.json` and the full list of every enabled report to this directory,
+    /// e.g. `graph.dot` and `orphans.txt`.
+    #[structopt(long, parse(from_os_str), conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+
+    /// Output format.
+    #[structopt(short = "f", long, default_value = "dot", possible_values = Format::NAMES)]
+    format: Format,
+
+    /// Render formats which refuse large graphs, like `matrix`, anyway.
+    #[structopt(long)]
+    force: bool,
+
+    /// In `edges` output, also list pages without any links, alone on their line. In `md-table`
+    /// output, as rows with an empty target.
+    #[structopt(long)]
+    list_isolated: bool,
+
+    /// Direction of the `dot` layout, e.g. `LR` to lay out pages left to right.
+    #[structopt(long, possible_values = format::dot::RANKDIRS)]
+    rankdir: Option<String>,
+
+    /// Graph attribute `key=value` for the header of `dot` output, can be given multiple times.
+    #[structopt(long, number_of_values = 1)]
+    dot_attr: Vec<format::dot::Attribute>,
+
+    /// Draw `dot` edges thicker the more often they are linked, or with `traffic` the more often
+    /// visitors followed them in the `--access-log`, with a legend.
+    #[structopt(long, possible_values = format::dot::WeightSource::NAMES)]
+    weight_source: Option<format::dot::WeightSource>,
+
+    /// Fill `dot` nodes by their depth from the root, green near it and red deep down, with a
+    /// legend. Pages it can't reach are grey.
+    #[structopt(long)]
+    color_by_depth: bool,
+
+    /// Colors of `--color-by-depth` from the root to the deepest pages, `#rrggbb` separated by
+    /// commas [default: #1a9850,#fee08b,#d73027].
+    #[structopt(long, requires = "color-by-depth")]
+    depth_palette: Option<format::dot::Palette>,
+
+    /// Add tooltips like `indeg=3 outdeg=5 depth=2` and the title to `dot` nodes, which
+    /// Graphviz shows on hover in SVG.
+    #[structopt(long)]
+    dot_tooltips: bool,
+
+    /// Label `dot` edges with this text, like `links`. They have no label by default.
+    #[structopt(long)]
+    edge_label: Option<String>,
+
+    /// IRI of the predicate for links in `turtle` output. Fragments of pages use `schema:hasPart`.
+    #[structopt(long, default_value = format::DEFAULT_RDF_PREDICATE)]
+    rdf_predicate: String,
+
+    /// Measurement name of `influx` output.
+    #[structopt(long, default_value = format::influx::DEFAULT_MEASUREMENT)]
+    influx_measurement: String,
+
+    /// Tag `key=value` for `influx` output, can be given multiple times.
+    #[structopt(long, number_of_values = 1)]
+    influx_tag: Vec<format::influx::Tag>,
+
+    /// Replace page names in the output with stable hashes, writing the mapping back to this file.
+    #[structopt(long, parse(from_os_str))]
+    hash_nodes: Option<PathBuf>,
+
     /// Find orphans.
     #[structopt(long)]
     find_orphans: Option<bool>,
+
+    /// Report the fewest orphans which, once linked, make all orphans reachable through the links
+    /// between them, with how many orphans each reconnects.
+    #[structopt(long)]
+    orphan_entries: bool,
+
+    /// Leave pages matching this regex out of the orphan reports, for pages unlinked on purpose
+    /// like `^thank-you$`. They stay in the graph. May be given several times.
+    #[structopt(long, number_of_values = 1)]
+    orphan_allow_regex: Vec<Regex>,
+
+    /// Match the names of `--root` and `--roots-from` against pages ignoring case, so `Index`
+    /// finds `index`. Names matching several pages which differ only in case are an error.
+    #[structopt(long)]
+    case_insensitive_lookup: bool,
+
+    /// The homepage, which orphans and depths are measured from [default: index].
+    #[structopt(long)]
+    root: Option<String>,
+
+    /// File listing further entry points, one page per line, which orphans are measured from too.
+    #[structopt(long, parse(from_os_str))]
+    roots_from: Option<PathBuf>,
+
+    /// Leave out everything but the largest group of pages connected by links, like stray test
+    /// pages, from the output and reports. The sizes of the groups left out are printed.
+    #[structopt(long)]
+    main_component_only: bool,
+
+    /// Merge paginated listings like `blog/page/2` into their first page `blog`, which gets
+    /// their links and the links to them.
+    #[structopt(long)]
+    collapse_pagination: bool,
+
+    /// Matches the part of page names which `--collapse-pagination` removes to name the parent.
+    #[structopt(long, default_value = r"/page/\d+")]
+    pagination_regex: Regex,
+
+    /// Measure orphans from the index page of each top-level directory too, like
+    /// `blog/index.html`, as sections are often only linked from their own landing page.
+    #[structopt(long)]
+    auto_section_roots: bool,
+
+    /// Guess the homepage from page names and inbound links, unless `--root` is given.
+    #[structopt(long)]
+    root_auto: bool,
+
+    /// Print at most this many entries of each report [default: 20 on a terminal, else all].
+    #[structopt(long)]
+    max_report: Option<usize>,
+
+    /// Report pages the root can't reach (forward), which can't reach the root (reverse), or both.
+    #[structopt(long, default_value = "forward", possible_values = Reachability::NAMES)]
+    reachability: Reachability,
+
+    /// Highlight pages whose file wasn't modified for this long (e.g. `2years`) in DOT output.
+    #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+    stale_after: Option<Duration>,
+
+    /// Crawl only this many files picked at random, for a quick look at a large site.
+    #[structopt(long)]
+    sample: Option<usize>,
+
+    /// Seed for `--sample`, to pick the same files again [default: random].
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Keep the html of crawled pages in a temporary on-disk database instead of in memory,
+    /// for sites too large for memory. Slower, as every page is written to disk and read back.
+    #[structopt(long, conflicts_with = "sample")]
+    spill: bool,
+
+    /// Write how long reading, parsing and normalizing each file took to this file, as a Chrome
+    /// trace to open in `chrome://tracing` or <https://ui.perfetto.dev>.
+    #[structopt(long, parse(from_os_str))]
+    profile: Option<PathBuf>,
+
+    /// Stop crawling after this long (e.g. `30s`, `2m`) and emit the partial graph.
+    #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+    timeout: Option<Duration>,
+
+    /// Report the deepest page reachable from the root and the click path to it.
+    #[structopt(long)]
+    max_depth_report: bool,
+
+    /// Combined log format access log; navigations in it become a `traffic` count on DOT edges,
+    /// navigations without a matching link are reported.
+    #[structopt(long, parse(from_os_str))]
+    access_log: Option<PathBuf>,
+
+    /// Report pages of this sitemap which are deeper in the graph than their priority suggests.
+    #[structopt(long, parse(from_os_str))]
+    compare_sitemaps_order: Option<PathBuf>,
+
+    /// Add the graph saved in this `json` file, if it exists, to the crawled one, then save the
+    /// combined graph back to it. Builds one graph from crawls of different parts of a site.
+    /// The file keeps links, links to other hosts, titles and modification times.
+    #[structopt(long, parse(from_os_str))]
+    merge_into: Option<PathBuf>,
+
+    /// Write graph statistics as JSON to this file.
+    #[structopt(long, parse(from_os_str))]
+    stats_json: Option<PathBuf>,
+
+    /// Write every finding, like orphans, dead ends and cycles, to this file as newline-delimited
+    /// JSON events, each with a `kind`, a `page` and where it applies a `target` and `detail`.
+    #[structopt(long, parse(from_os_str))]
+    events_out: Option<PathBuf>,
+
+    /// Fail if any page links to itself.
+    #[structopt(long)]
+    fail_on_self_link: bool,
+
+    /// Keep links from pages to themselves in the graph, like pagination links. They are
+    /// left out otherwise, but still reported as self-links.
+    #[structopt(long)]
+    keep_self_links: bool,
+
+    /// Report the inbound links of each path prefix, most linked first.
+    #[structopt(long)]
+    top_paths: bool,
+
+    /// Number of path segments `--top-paths` groups pages by.
+    #[structopt(long, default_value = "1")]
+    aggregate_depth: usize,
+
+    /// Report pages whose only outgoing link is to the root.
+    #[structopt(long)]
+    leaf_report: bool,
+
+    /// Report pairs of pages linking to each other, a sign of redirect loops, and larger
+    /// groups of pages linking to each other in cycles.
+    #[structopt(long)]
+    report_mutual_links: bool,
+
+    /// Report links between pages which should link to each other but only link one way.
+    /// Matched against `page -> other` of each link in either direction, like
+    /// `^shop/[^/]+ -> shop$` for products and their category.
+    #[structopt(long)]
+    reciprocal_regex: Option<Regex>,
+
+    /// Report pages reachable from the root with fewer than this many inbound links.
+    #[structopt(long)]
+    min_inlinks: Option<usize>,
+
+    /// Report pages linking to more than this many other pages, most first.
+    #[structopt(long)]
+    max_outlinks: Option<usize>,
+
+    /// Fail if any page links to more pages than `--max-outlinks`.
+    #[structopt(long, requires = "max-outlinks")]
+    fail_on_max_outlinks: bool,
+
+    /// Report pages containing placeholder links like `href="#"` or `href=""`.
+    #[structopt(long)]
+    report_empty_links: bool,
+
+    /// Report scripts, images, styles and other resources loaded over `http://` by pages
+    /// declaring an `https://` URL with a canonical link or `og:url`.
+    #[structopt(long)]
+    report_mixed_content: bool,
+
+    /// Report pages which are only linked from boilerplate like navigation or footers.
+    #[structopt(long)]
+    content_orphans: bool,
+
+    /// Fraction of pages which must link to a page for those links to count as boilerplate.
+    #[structopt(long, default_value = "0.8")]
+    boilerplate_threshold: f64,
+
+    /// Keep trailing slashes, for sites where `page` and `page/` are different pages.
+    #[structopt(long)]
+    keep_trailing_slash: bool,
+
+    /// Treat links to `page/index.html` as links to `page/`.
+    #[structopt(long)]
+    normalize_trailing_index: bool,
+
+    /// Drop query strings from links, so `page/?ref=x` is `page`.
+    #[structopt(long)]
+    strip_query: bool,
+
+    /// Spell percent-encoding in page names and links the same way, so `caf%C3%A9` and `café`
+    /// are one page.
+    #[structopt(long)]
+    normalize_percent_encoding: bool,
+
+    /// Whether `--normalize-percent-encoding` decodes paths, like file names, or encodes them.
+    #[structopt(long, default_value = "decode", possible_values = PercentMode::NAMES)]
+    percent_mode: PercentMode,
+
+    /// Keep the hosts of off-site links as extra nodes in the output, without links of their own.
+    /// Reports and stats still only cover the site.
+    #[structopt(long)]
+    include_external_nodes: bool,
+
+    /// Leave out pages with `<meta name="robots" content="noindex">` and the links of pages with
+    /// `nofollow`, as search engines do.
+    #[structopt(long)]
+    respect_meta_robots: bool,
+
+    /// Drop links with this text, compared case-insensitively, e.g. "Back to top".
+    /// Can be given multiple times.
+    #[structopt(long, number_of_values = 1)]
+    drop_anchor_text: Vec<String>,
+
+    /// Leave out links with `rel="nofollow"`, `sponsored` or `ugc`, as search engines do.
+    #[structopt(long)]
+    follow_only: bool,
+
+    /// Report how many links are followed and how many have `rel="nofollow"`, `sponsored` or `ugc`.
+    #[structopt(long)]
+    rel_report: bool,
+
+    /// Keep links to `page#section` as nodes contained in `page` instead of stripping fragments.
+    #[structopt(long)]
+    track_fragments: bool,
+
+    /// Report the fragments links pointed at for each page, as they are stripped from the graph.
+    #[structopt(long)]
+    fragment_report: bool,
+
+    /// Report links to `page#section` and `#section` where the page has no element with that
+    /// `id`, or `<a name>`, together with the page linking.
+    #[structopt(long)]
+    check_anchors: bool,
+
+    /// Report links to pages of the site which weren't read, with the page linking. When the whole
+    /// site is read, these are broken links.
+    #[structopt(long)]
+    check_local_links: bool,
+
+    /// Report pages linked with several different link texts, like "Pricing", "Plans" and "See
+    /// prices", with the texts, so they can be made consistent.
+    #[structopt(long)]
+    anchor_variance_report: bool,
+}
+
+/// Exit code used when `--timeout` expired, same as coreutils `timeout`.
+const EXIT_TIMED_OUT: i32 = 124;
+
+/// Exit code used when the crawl was interrupted with Ctrl-C, as shells report SIGINT.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// How reading the pages ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Crawl {
+    Complete,
+    /// `--timeout` expired.
+    TimedOut,
+    /// Ctrl-C was pressed.
+    Interrupted,
+}
+
+impl From<Stop> for Crawl {
+    fn from(stop: Stop) -> Self {
+        match stop {
+            Stop::TimedOut => Crawl::TimedOut,
+            Stop::Cancelled => Crawl::Interrupted,
+        }
+    }
+}
+
+/// Default of `--max-report` when printing to a terminal.
+const INTERACTIVE_REPORT_LIMIT: usize = 20;
+
+/// How many different link texts a page needs for `--anchor-variance-report`.
+const MIN_LINK_TEXT_VARIANTS: usize = 3;
+
+/// Prints lists of pages, truncated to keep interactive runs readable.
+struct Reporter<'a> {
+    /// Print at most this many entries of each list.
+    limit: Option<usize>,
+    /// Full lists of truncated reports are written next to this file.
+    output: Option<&'a Path>,
+    /// Full lists of all reports are written to this directory.
+    dir: Option<&'a Path>,
+}
+
+impl Reporter<'_> {
+    /// The limit of `--max-report`, by default `INTERACTIVE_REPORT_LIMIT` when printing to a
+    /// terminal and none when piped.
+    fn default_limit(max_report: Option<usize>, is_terminal: bool) -> Option<usize> {
+        max_report.or(Some(INTERACTIVE_REPORT_LIMIT).filter(|_| is_terminal))
+    }
+
+    /// Print `label` and `items`. `name` names the file the full list is written to.
+    fn list(&self, name: &str, label: &str, items: &[&str]) -> Result<(), anyhow::Error> {
+        self.write_list(&mut std::io::stdout().lock(), name, label, items)
+    }
+
+    /// Like `list`, writing to `out` instead of stdout.
+    fn write_list(
+        &self,
+        out: &mut dyn Write,
+        name: &str,
+        label: &str,
+        items: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        writeln!(out, "{} ({}):", label, items.len())?;
+        let shown = self.limit.unwrap_or(items.len()).min(items.len());
+        for item in &items[..shown] {
+            writeln!(out, "  {}", item)?;
+        }
+
+        let full = match (self.dir, self.output) {
+            (Some(dir), _) => Some(dir.join(format!("{}.txt", name))),
+            (None, Some(output)) if shown < items.len() => {
+                Some(output.with_extension(format!("{}.txt", name)))
+            }
+            (None, _) => None,
+        };
+        if let Some(path) = &full {
+            let mut list = items.join("\n");
+            if !list.is_empty() {
+                list.push('\n');
+            }
+            fs::write(path, list).context(format!("Could not write to {}", path.display()))?;
+        }
+
+        let hidden = items.len() - shown;
+        match (hidden, full) {
+            (0, _) => {}
+            (_, Some(path)) => writeln!(out, "  ... and {} more, see {}", hidden, path.display())?,
+            (_, None) => writeln!(out, "  ... and {} more", hidden)?,
+        }
+        Ok(())
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let opt = Opt::from_args();
 
-    if !opt.directory.is_dir() {
-        anyhow::bail!(format!("{} is not a directory", opt.directory.display()));
+    let profile = opt.profile.as_ref().map(|path| {
+        let (layer, guard) = profile_layer(path);
+        tracing_subscriber::registry().with(layer).init();
+        guard
+    });
+
+    // The first Ctrl-C stops the crawl, keeping the partial graph, the second quits.
+    let budget = Budget::default();
+    let cancel = budget.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancel.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    });
+
+    let crawl = match &opt.manifest {
+        Some(path) => run_manifest(&opt, path, &budget).await?,
+        None => run(&opt, &budget).await?,
+    };
+
+    // Exiting skips destructors, write the trace before.
+    drop(profile);
+    if let Some(code) = exit_code(crawl) {
+        std::process::exit(code);
     }
 
-    // Maps page names to URLs they link to.
-    let mut map = HashMap::new();
+    Ok(())
+}
 
-    // Read all files in given directory.
-    let paths = fs::read_dir(opt.directory)
-        .unwrap()
-        .map(|p| p.unwrap().path());
+/// A layer writing the spans to a Chrome trace at `path` for `--profile`, when the guard drops.
+fn profile_layer<S>(path: &Path) -> (ChromeLayer<S>, FlushGuard)
+where
+    S: Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+{
+    ChromeLayerBuilder::new()
+        .file(path)
+        .include_args(true)
+        // One pair of events per span, not per poll of the async reads.
+        .trace_style(TraceStyle::Async)
+        .build()
+}
 
-    // Crawl html files.
-    for path in paths {
-        let file = fs::read_to_string(&path).unwrap();
+/// The exit code telling how the crawl ended, `None` to exit normally.
+fn exit_code(crawl: Crawl) -> Option<i32> {
+    match crawl {
+        Crawl::Complete => None,
+        Crawl::TimedOut => Some(EXIT_TIMED_OUT),
+        Crawl::Interrupted => Some(EXIT_INTERRUPTED),
+    }
+}
 
-        let key = path.file_name().unwrap().to_str().unwrap().to_string();
+/// Process every site of the manifest at `path`, with the other settings of `opt`. A site
+/// failing doesn't stop the others, but Ctrl-C does. Ends with a summary of all sites.
+async fn run_manifest(opt: &Opt, path: &Path, budget: &Budget) -> Result<Crawl, anyhow::Error> {
+    let manifest = Manifest::load(path)?;
 
-        let urls = get_urls_from(&file);
+    let mut crawl = Crawl::Complete;
+    let mut outcomes = Vec::new();
+    let mut failed = 0;
+    for site in &manifest.sites {
+        println!("== {} ==", site.directory.display());
+        let site_opt = Opt {
+            directory: Some(site.directory.clone()),
+            manifest: None,
+            domain: site.domain.clone().or_else(|| opt.domain.clone()),
+            root: site.root.clone().or_else(|| opt.root.clone()),
+            output: site.output.clone(),
+            ..opt.clone()
+        };
+        let outcome = match run(&site_opt, budget).await {
+            Ok(Crawl::Complete) => "ok".to_string(),
+            Ok(Crawl::TimedOut) => {
+                crawl = Crawl::TimedOut;
+                "timed out".to_string()
+            }
+            Ok(Crawl::Interrupted) => {
+                crawl = Crawl::Interrupted;
+                outcomes.push((site, "interrupted".to_string()));
+                break;
+            }
+            Err(error) => {
+                eprintln!("error: {:#}", error);
+                failed += 1;
+                format!("failed: {:#}", error)
+            }
+        };
+        outcomes.push((site, outcome));
+    }
 
-        // Filter out all non-traplinked urls
-        let urls = filter_regex(&urls, &FILTER_TRAPL_URLS);
+    println!(
+        "sites ({} of {} processed, {} failed):",
+        outcomes.len(),
+        manifest.sites.len(),
+        failed
+    );
+    for (site, outcome) in &outcomes {
+        println!("  {}: {}", site.directory.display(), outcome);
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} sites failed", failed, manifest.sites.len());
+    }
+    Ok(crawl)
+}
 
-        let tags: Vec<_> = urls
+/// Build the graph of the site in `opt.directory` and write the output and reports.
+/// Ctrl-C is handled through `budget`, which stops the crawl.
+async fn run(opt: &Opt, budget: &Budget) -> Result<Crawl, anyhow::Error> {
+    let base = match &opt.domain {
+        Some(domain) => Config::for_domain(domain),
+        None => Config::default(),
+    };
+    let base = match &opt.canonical_host {
+        Some(host) => base.with_canonical_host(host),
+        None => base,
+    };
+    let config = Config {
+        keep_trailing_slash: opt.keep_trailing_slash,
+        collapse_index: opt.normalize_trailing_index,
+        track_fragments: opt.track_fragments,
+        strip_query: opt.strip_query,
+        follow_only: opt.follow_only,
+        include_external: opt.include_external_nodes,
+        drop_anchor_texts: opt.drop_anchor_text.clone(),
+        respect_meta_robots: opt.respect_meta_robots,
+        check_anchors: opt.check_anchors,
+        keep_self_links: opt.keep_self_links,
+        record_link_texts: opt.anchor_variance_report,
+        percent_mode: Some(opt.percent_mode).filter(|_| opt.normalize_percent_encoding),
+        ..base
+    };
+
+    if !opt.compare.is_empty() {
+        print_comparison(opt)?;
+        return Ok(Crawl::Complete);
+    }
+
+    if let Some(path) = &opt.explain {
+        let content =
+            fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+        let page = path.file_name().unwrap_or_default().to_string_lossy();
+        print_explanation(&page, &content, &config);
+        return Ok(Crawl::Complete);
+    }
+
+    let (page_graph, crawl, mut mixed_content) = match &opt.links_from {
+        Some(path) => {
+            let json =
+                fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+            let page_graph = load_links(&json)
+                .context(format!("Could not parse links in {}", path.display()))?;
+            (page_graph, Crawl::Complete, Vec::new())
+        }
+        None if opt.stdin => {
+            let stdin = std::io::stdin();
+            let page_graph = build_graph_from_reader(&opt.stdin_name, stdin.lock(), &config)?;
+            (page_graph, Crawl::Complete, Vec::new())
+        }
+        None => crawl(opt, &config, budget).await?,
+    };
+    if let Some(path) = &opt.dump_links {
+        fs::write(path, dump_links(&page_graph)?)
+            .context(format!("Could not write to {}", path.display()))?;
+    }
+    let page_graph = match &opt.merge_into {
+        Some(path) if path.exists() => {
+            let json =
+                fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+            let saved = format::json::parse(&json)
+                .context(format!("Could not parse graph in {}", path.display()))?;
+            page_graph.merged_with(saved)
+        }
+        _ => page_graph,
+    };
+    if let Some(path) = &opt.merge_into {
+        let json = format::json::render(&page_graph.with_external_nodes())?;
+        fs::write(path, json).context(format!("Could not write to {}", path.display()))?;
+    }
+    let page_graph = if opt.collapse_pagination {
+        let parents = page_graph.pagination_parents(&opt.pagination_regex);
+        let distinct: HashSet<_> = parents.values().collect();
+        println!(
+            "collapsed {} into {}",
+            plural(parents.len(), "pagination page"),
+            plural(distinct.len(), "parent")
+        );
+        page_graph.with_pages_merged(&parents)
+    } else {
+        page_graph
+    };
+    let page_graph = if opt.main_component_only {
+        let components = find_components(&page_graph.graph())
             .into_iter()
-            .map(|u| filter_prefix(&u, &TRAPL_PREFIXES))
-            .map(remove_trailing_slash)
-            .filter(|s| is_crawling_leftover(s))
-            .collect();
+            .map(|pages| pages.into_iter().map(str::to_string).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        match components.split_first() {
+            Some((main, others)) => {
+                let sizes: Vec<_> = others.iter().map(|pages| pages.len().to_string()).collect();
+                if sizes.is_empty() {
+                    println!("main component: {} pages, no others", main.len());
+                } else {
+                    println!(
+                        "main component: {} pages, left out {} other components of {} pages",
+                        main.len(),
+                        others.len(),
+                        sizes.join(", ")
+                    );
+                }
+                let main: HashSet<_> = main.iter().map(String::as_str).collect();
+                // Entries are `page: url (kind)`.
+                mixed_content.retain(|entry| {
+                    let (page, _) = entry.split_once(": ").unwrap_or_default();
+                    main.contains(page)
+                });
+                page_graph.restricted_to(&main)
+            }
+            None => page_graph,
+        }
+    } else {
+        page_graph
+    };
+    mixed_content.sort_unstable();
+    if opt.respect_meta_robots {
+        println!(
+            "meta robots: left out {} noindex pages and the links of {} nofollow pages",
+            page_graph.noindex_pages().len(),
+            page_graph.nofollow_pages().len()
+        );
+    }
+    let graph = page_graph.graph();
+    let self_links = page_graph.self_link_pages();
+    let dropped: usize = page_graph.dropped_self_links().values().sum();
+    if dropped > 0 {
+        eprintln!(
+            "note: left out {} of {} to themselves, keep them with --keep-self-links",
+            plural(dropped, "link"),
+            plural(page_graph.dropped_self_links().len(), "page")
+        );
+    }
+
+    let root = match (&opt.root, opt.root_auto) {
+        (Some(root), _) => root.as_str(),
+        (None, true) => {
+            let root = guess_root(&graph).unwrap_or("index");
+            eprintln!("using {} as root", root);
+            root
+        }
+        (None, false) => "index",
+    };
+    let root = if opt.case_insensitive_lookup {
+        resolve_ignoring_case(&graph, root)?
+    } else {
+        root
+    };
+
+    // Pages orphans are counted from, for the reports as well as the stats of the output.
+    let list = roots_list(opt)?;
+    let (roots, sections) = find_roots(opt, &graph, root, &list)?;
+
+    // Generate the output in the requested format.
+    let traffic = match &opt.access_log {
+        Some(path) => {
+            let log =
+                fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+            parse_access_log(&log, root, &config)
+        }
+        None => Default::default(),
+    };
 
-        map.insert(key, tags);
+    let allowed_orphans: Vec<_> = graph
+        .nodes()
+        .filter(|page| {
+            opt.orphan_allow_regex
+                .iter()
+                .any(|allow| allow.is_match(page))
+        })
+        .collect();
+    let mut options = RenderOptions {
+        root: root.to_string(),
+        roots: roots[1..].iter().map(|page| page.to_string()).collect(),
+        allowed_orphans: allowed_orphans
+            .iter()
+            .map(|page| page.to_string())
+            .collect(),
+        base_url: format!("https://{}/", config.domain),
+        rdf_predicate: opt.rdf_predicate.clone(),
+        traffic: traffic.clone(),
+        force: opt.force,
+        stale_before: opt
+            .stale_after
+            .and_then(|age| SystemTime::now().checked_sub(age)),
+        isolated: opt.list_isolated,
+        dot_attributes: opt
+            .rankdir
+            .iter()
+            .map(|rankdir| format::dot::Attribute::new("rankdir", rankdir))
+            .chain(opt.dot_attr.iter().cloned())
+            .collect(),
+        edge_label: opt.edge_label.clone(),
+        weight_source: opt.weight_source,
+        depth_palette: opt
+            .color_by_depth
+            .then(|| opt.depth_palette.clone().unwrap_or_default()),
+        tooltips: opt.dot_tooltips,
+        influx_measurement: opt.influx_measurement.clone(),
+        influx_tags: opt.influx_tag.clone(),
+        timestamp: None,
+    };
+    let external;
+    let output_graph = if opt.include_external_nodes {
+        external = page_graph.with_external_nodes();
+        &external
+    } else {
+        &page_graph
+    };
+    let node_count = output_graph.graph().node_count();
+    if opt.format == Format::Pdf && node_count > format::pdf::MAX_NODES {
+        eprintln!(
+            "warning: {} pages are more than a PDF drawing shows readably (at most {})",
+            node_count,
+            format::pdf::MAX_NODES
+        );
     }
+    let anonymized;
+    let output_graph = if let Some(path) = &opt.hash_nodes {
+        let (graph, mapping) = anonymize(output_graph);
+        anonymized = graph;
+        let mapping: String = mapping
+            .iter()
+            .map(|(hash, name)| format!("{}\t{}\n", hash, name))
+            .collect();
+        fs::write(path, mapping).context(format!("Could not write to {}", path.display()))?;
 
-    // Make a petgraph `GraphMap` from the page name -> URLs map.
-    let graph = make_page_graph(&map);
+        options.root = hash_name(root);
+        options.roots = roots[1..].iter().map(|page| hash_name(page)).collect();
+        options.allowed_orphans = allowed_orphans.iter().map(|page| hash_name(page)).collect();
+        options.traffic = traffic
+            .iter()
+            .map(|((from, to), &count)| ((hash_name(from), hash_name(to)), count))
+            .collect();
+        &anonymized
+    } else {
+        output_graph
+    };
+    let mut result = Vec::new();
+    opt.format
+        .writer(&options)
+        .write(output_graph, &mut result)?;
 
-    // Generate the output in dot format.
-    let result = format!("{:?}", Dot::with_config(&graph, &[Config::EdgeNoLabel]));
+    if let Some(dir) = &opt.output_dir {
+        fs::create_dir_all(dir).context(format!("Could not create {}", dir.display()))?;
+    }
+    let output = opt.output.clone().or_else(|| {
+        let dir = opt.output_dir.as_ref()?;
+        Some(dir.join("graph").with_extension(opt.format.extension()))
+    });
 
     // Save result to output file or write to stdout.
-    if let Some(path) = opt.output {
+    if let Some(path) = &output {
         let mut file = OpenOptions::new()
             .write(true)
+            .create(true)
             .truncate(true)
-            .open(&path)
+            .open(path)
             .context("Could not open output file for writing")?;
-        file.write(result.as_bytes())
+        file.write_all(&result)
             .context(format!("Could not write to {}", path.display()))?;
     } else {
         // print to stdout if no output file requested.
-        println!("{}", result);
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&result)?;
+        writeln!(stdout)?;
     };
 
+    let reporter = Reporter {
+        limit: Reporter::default_limit(opt.max_report, std::io::stdout().is_terminal()),
+        output: opt.output.as_deref(),
+        dir: opt.output_dir.as_deref(),
+    };
+
+    if opt.auto_section_roots {
+        reporter.list("section-roots", "inferred section roots", &sections)?;
+    }
+
+    if let Some(path) = &opt.events_out {
+        let events = to_ndjson(&find_events(&page_graph, &roots))?;
+        fs::write(path, events).context(format!("Could not write to {}", path.display()))?;
+    }
+
     if let Some(true) = opt.find_orphans {
-        let orphans = find_orphans(&graph);
+        let mut orphans = find_unreachable(&graph, &roots, opt.reachability);
+        let allowed = remove_allowed_orphans(&mut orphans, &opt.orphan_allow_regex);
+        let mut orphans: Vec<_> = orphans.into_iter().collect();
+        orphans.sort_unstable();
 
-        println!("orphan candidates: {:?}", orphans);
+        reporter.list("orphans", "orphan candidates", &orphans)?;
+        if allowed > 0 {
+            println!("left out {} orphans matching --orphan-allow-regex", allowed);
+        }
+
+        let sections = group_by_section(&orphans);
+        if sections.len() > 1 {
+            println!("orphans by section:");
+            for (section, pages) in &sections {
+                let section = if section.is_empty() {
+                    "(top level)".to_string()
+                } else {
+                    format!("{}/", section)
+                };
+                println!("  {}: {}", section, pages.len());
+            }
+        }
+        reporter.list("self-links", "self-links", &self_links)?;
     }
 
-    Ok(())
+    if opt.orphan_entries {
+        let mut orphans = find_unreachable(&graph, &roots, Reachability::Forward);
+        remove_allowed_orphans(&mut orphans, &opt.orphan_allow_regex);
+        let entries: Vec<_> = find_orphan_entries(&graph, &orphans)
+            .into_iter()
+            .map(|(page, reconnected)| format!("{} ({})", page, reconnected))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("orphan-entries", "orphans to link to", &entries)?;
+    }
+
+    if opt.access_log.is_some() {
+        let entries: Vec<_> = find_unexpected(&traffic, &graph)
+            .into_iter()
+            .map(|(from, to, count)| format!("{} -> {} ({})", from, to, count))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list(
+            "unexpected-navigation",
+            "navigations without a link",
+            &entries,
+        )?;
+    }
+
+    if opt.fragment_report {
+        let mut pages: Vec<_> = page_graph.fragments().iter().collect();
+        pages.sort_unstable();
+        let entries: Vec<_> = pages
+            .into_iter()
+            .map(|(page, fragments)| {
+                let fragments: Vec<_> = fragments.iter().map(String::as_str).collect();
+                format!("{}: {}", page, fragments.join(", "))
+            })
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("fragments", "linked fragments", &entries)?;
+    }
+
+    if opt.check_anchors {
+        let entries: Vec<_> = page_graph
+            .broken_anchors()
+            .into_iter()
+            .map(|(page, target, fragment)| format!("{} -> {}#{}", page, target, fragment))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("broken-anchors", "links to missing anchors", &entries)?;
+    }
+
+    if opt.check_local_links {
+        let entries: Vec<_> = page_graph
+            .missing_link_targets()
+            .into_iter()
+            .map(|(page, target)| format!("{} -> {}", page, target))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("broken-links", "links to pages that weren't read", &entries)?;
+    }
+
+    if opt.anchor_variance_report {
+        let entries: Vec<_> = page_graph
+            .link_text_variants(MIN_LINK_TEXT_VARIANTS)
+            .into_iter()
+            .map(|(target, texts)| {
+                let texts: Vec<_> = texts.iter().map(|text| format!("\"{}\"", text)).collect();
+                format!("{}: {}", target, texts.join(", "))
+            })
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list(
+            "anchor-variance",
+            "pages linked with different texts",
+            &entries,
+        )?;
+    }
+
+    if opt.top_paths {
+        let entries: Vec<_> = inbound_by_prefix(&graph, opt.aggregate_depth)
+            .into_iter()
+            .map(|(prefix, inlinks)| format!("{} ({})", prefix, inlinks))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("top-paths", "inbound links by path", &entries)?;
+    }
+
+    if opt.rel_report {
+        let entries: Vec<_> = count_by_rel(&page_graph)
+            .into_iter()
+            .map(|(rel, count)| format!("{} ({})", rel, count))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("rels", "links by rel", &entries)?;
+    }
+
+    if opt.report_mutual_links {
+        let entries: Vec<_> = find_mutual_links(&graph)
+            .into_iter()
+            .map(|(page, other)| format!("{} <-> {}", page, other))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+        reporter.list("mutual-links", "pages linking to each other", &entries)?;
+
+        let entries: Vec<_> = find_closed_cycles(&graph)
+            .into_iter()
+            .map(|pages| pages.join(", "))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+        reporter.list("closed-cycles", "cycles of more than two pages", &entries)?;
+    }
+
+    if let Some(pairs) = &opt.reciprocal_regex {
+        let entries: Vec<_> = find_missing_back_links(&graph, pairs)
+            .into_iter()
+            .map(|(page, other)| format!("{} -> {}", page, other))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+        reporter.list("missing-back-links", "links without a link back", &entries)?;
+    }
+
+    if opt.leaf_report {
+        reporter.list(
+            "leaves",
+            "pages only linking to the root",
+            &find_leaf_pages(&graph, root),
+        )?;
+    }
+
+    if let Some(min) = opt.min_inlinks {
+        let entries: Vec<_> = find_under_linked(&graph, root, min)
+            .into_iter()
+            .map(|(page, inlinks)| format!("{} ({})", page, inlinks))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("under-linked", "under-linked pages", &entries)?;
+    }
+
+    let link_heavy = match opt.max_outlinks {
+        Some(max) => find_link_heavy(&graph, max),
+        None => Vec::new(),
+    };
+    if opt.max_outlinks.is_some() {
+        let entries: Vec<_> = link_heavy
+            .iter()
+            .map(|(page, outlinks)| format!("{} ({})", page, outlinks))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("link-heavy", "pages with too many outbound links", &entries)?;
+    }
+
+    if opt.report_empty_links {
+        let mut pages: Vec<_> = page_graph.placeholder_links().iter().collect();
+        pages.sort_unstable();
+        let entries: Vec<_> = pages
+            .into_iter()
+            .map(|(page, count)| format!("{} ({})", page, count))
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("empty-links", "pages with placeholder links", &entries)?;
+    }
+
+    if opt.report_mixed_content {
+        let entries: Vec<_> = mixed_content.iter().map(String::as_str).collect();
+
+        reporter.list("mixed-content", "mixed content", &entries)?;
+    }
+
+    if opt.content_orphans {
+        let orphans = find_content_orphans(page_graph.links(), root, opt.boilerplate_threshold);
+
+        reporter.list("content-orphans", "content orphan candidates", &orphans)?;
+    }
+
+    if opt.max_depth_report {
+        match deepest_page(&graph, root) {
+            Some(deepest) => {
+                println!(
+                    "max depth: {} (deepest page: {})",
+                    deepest.depth,
+                    deepest.path.last().unwrap()
+                );
+                println!("path: {}", deepest.path.join(" -> "));
+            }
+            None => println!("max depth: {} is not in the graph", root),
+        }
+    }
+
+    if let Some(path) = &opt.compare_sitemaps_order {
+        let xml = fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+        let pages: Vec<_> = graph.nodes().collect();
+        let buried = find_buried_pages(
+            &sitemap::parse(&xml),
+            &pages,
+            &depths(&graph, root),
+            root,
+            &config,
+        );
+        let entries: Vec<_> = buried
+            .iter()
+            .map(|b| {
+                let depth = b.depth.map_or("unreachable".to_string(), |d| d.to_string());
+                format!(
+                    "{} (priority {}, depth {}, {} places lower)",
+                    b.page, b.priority, depth, b.displacement
+                )
+            })
+            .collect();
+        let entries: Vec<_> = entries.iter().map(String::as_str).collect();
+
+        reporter.list("buried", "buried sitemap pages", &entries)?;
+    }
+
+    let stats_json = opt.stats_json.clone().or_else(|| {
+        let dir = opt.output_dir.as_ref()?;
+        Some(dir.join("stats.json"))
+    });
+    if let Some(path) = &stats_json {
+        let stats = GraphStats::new(&graph, &roots);
+        let json = serde_json::to_string_pretty(&stats)?;
+        fs::write(path, json).context(format!("Could not write to {}", path.display()))?;
+    }
+
+    if opt.fail_on_self_link && !self_links.is_empty() {
+        anyhow::bail!(
+            "{} pages link to themselves: {:?}",
+            self_links.len(),
+            self_links
+        );
+    }
+    if opt.fail_on_max_outlinks && !link_heavy.is_empty() {
+        let pages: Vec<_> = link_heavy.iter().map(|(page, _)| *page).collect();
+        anyhow::bail!(
+            "{} pages link to more than {} pages: {:?}",
+            pages.len(),
+            opt.max_outlinks.unwrap_or_default(),
+            pages
+        );
+    }
+
+    Ok(crawl)
 }
 
-/// Find orphans in the given `graph`.
-pub fn find_orphans<'a>(graph: &'a GraphMap<&str, &str, Directed>) -> HashSet<&'a str> {
-    // A list of all pages.
-    let mut orphans: HashSet<&'a str> = graph.nodes().into_iter().collect();
+/// Read the pages of `opt.directory` and build their graph, which is partial if `--timeout`
+/// expired or `budget` was cancelled while reading or building. Also gives the mixed content
+/// found, if asked for.
+async fn crawl(
+    opt: &Opt,
+    config: &Config,
+    budget: &Budget,
+) -> Result<(PageGraph, Crawl, Vec<String>), anyhow::Error> {
+    let budget = budget.with_timeout(opt.timeout);
+    let directory = opt
+        .directory
+        .as_ref()
+        .expect("required unless explaining or loading links");
+    let archive = directory.is_file() && is_archive(directory);
+    if !directory.is_dir() && !archive {
+        anyhow::bail!(format!("{} is not a directory", directory.display()));
+    }
 
-    // Attempt to visit all pages reachable from index.html.
-    let mut dfs = Dfs::new(&graph, "index");
+    // Pages are built as they are read, unless they all need to be read first: to be kept on
+    // disk with `--spill`, or to sample an archive.
+    let mut builder = MixedContentCheck::new(GraphBuilder::new(config), opt.report_mixed_content);
+    let mut limited = Limited::new(&mut builder, &budget);
+    let mut pages = Vec::new();
+    let mut spilled = if opt.spill {
+        Some(SpilledPages::new()?)
+    } else {
+        None
+    };
+    let deferred = spilled.is_some() || (archive && opt.sample.is_some());
+    let mut checked = NameCheck::new(match &mut spilled {
+        Some(spilled) => spilled,
+        None if deferred => &mut pages,
+        None => &mut limited,
+    });
+    let sink: &mut dyn PageSink = &mut checked;
+    // Page names and when their file was last modified.
+    let mut modified = HashMap::new();
 
-    while let Some(v) = dfs.next(&graph) {
-        // All visited pages are reachable, so not orphans.
-        orphans.remove(v);
+    let seed = opt.seed.unwrap_or_else(rand::random);
+    // Number of files when only a sample of them is read.
+    let mut total = None;
+
+    // Read and build pages, giving up early on timeout or Ctrl-C. Reading an archive doesn't
+    // wait, so it checks the budget before each entry itself.
+    let mut crawl = if archive {
+        let _crawl = info_span!("crawl").entered();
+        read_archive(directory, sink, &budget)?.map_or(Crawl::Complete, Crawl::from)
+    } else {
+        let read = async {
+            match opt.sample {
+                Some(size) => read_sample(directory, size, seed, sink, &mut modified)
+                    .await
+                    .map(Some),
+                None => read_pages_with_modified(directory, sink, &mut modified)
+                    .await
+                    .map(|()| None),
+            }
+        };
+        tokio::select! {
+            biased;
+            stop = budget.exhausted() => stop.into(),
+            result = read.instrument(info_span!("crawl")) => {
+                total = result?;
+                Crawl::Complete
+            }
+        }
+    };
+
+    let mut crawled = sink.page_count();
+    let duplicates = checked.duplicates();
+    if !duplicates.is_empty() {
+        let names: Vec<_> = duplicates.iter().map(String::as_str).collect();
+        eprintln!(
+            "warning: {} pages read from more than one file, keeping the last of each: {}",
+            names.len(),
+            names.join(", ")
+        );
+    }
+    if let (true, Some(size)) = (archive, opt.sample) {
+        total = Some(pages.len());
+        pages = sample(pages, size, seed);
+        crawled = pages.len();
+    }
+
+    if let Some(total) = total {
+        eprintln!(
+            "note: graph of a sample of {} of {} files (seed {})",
+            crawled, total, seed
+        );
     }
 
-    orphans
+    if deferred {
+        let mut spill_error = None;
+        let pages: Box<dyn Iterator<Item = (String, String)>> = match &spilled {
+            Some(spilled) => Box::new(
+                spilled
+                    .iter()
+                    .map_while(|page| page.map_err(|e| spill_error = Some(e)).ok()),
+            ),
+            None => Box::new(std::mem::take(&mut pages).into_iter()),
+        };
+        info_span!("build").in_scope(|| {
+            for (name, content) in pages {
+                limited.push_page(name, content)?;
+                if limited.stopped().is_some() {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+        if let Some(error) = spill_error {
+            return Err(error.context("Could not read spilled pages"));
+        }
+    }
+    if let (Crawl::Complete, Some(stop)) = (crawl, limited.stopped()) {
+        crawl = stop.into();
+    }
+
+    let built = limited.page_count();
+    match crawl {
+        Crawl::Complete => {}
+        Crawl::TimedOut => eprintln!(
+            "warning: timed out after {}, graph is partial ({} of {} pages built)",
+            humantime::format_duration(opt.timeout.unwrap_or_default()),
+            built,
+            crawled
+        ),
+        Crawl::Interrupted => eprintln!(
+            "warning: interrupted, graph is partial ({} of {} pages built). Press Ctrl-C again to quit",
+            built, crawled
+        ),
+    }
+    let (builder, mixed_content) = builder.into_parts();
+    let page_graph = info_span!("build")
+        .in_scope(|| builder.finish())
+        .with_modified(modified);
+    Ok((page_graph, crawl, mixed_content))
 }
 
-pub fn make_page_graph(data: &HashMap<String, Vec<String>>) -> GraphMap<&str, &str, Directed> {
-    let mut graph = petgraph::graphmap::GraphMap::<&str, &str, Directed>::new();
+/// Passes pages on to another `PageSink`, noting their mixed content for
+/// `--report-mixed-content` as `page: url (kind)` if `enabled`.
+struct MixedContentCheck<S> {
+    sink: S,
+    enabled: bool,
+    found: Vec<String>,
+}
 
-    for (k, values) in data {
-        graph.add_node(k.as_str());
-        for value in values {
-            graph.add_node(value.as_str());
-            graph.add_edge(k.as_str(), value.as_str(), "links");
+impl<S: PageSink> MixedContentCheck<S> {
+    fn new(sink: S, enabled: bool) -> Self {
+        Self {
+            sink,
+            enabled,
+            found: Vec::new(),
         }
     }
-    graph
+
+    /// The sink and the mixed content found, in the order of the pages.
+    fn into_parts(self) -> (S, Vec<String>) {
+        (self.sink, self.found)
+    }
 }
 
-/// Make a new vec which only contains the Strings matching the regex.
-pub fn filter_regex(items: &[String], regex: &Regex) -> Vec<String> {
-    items
-        .iter()
-        .filter(|s| regex.is_match(s))
-        .cloned()
-        .collect()
+impl<S: PageSink> PageSink for MixedContentCheck<S> {
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        if self.enabled {
+            for reference in find_mixed_content(&content) {
+                self.found
+                    .push(format!("{}: {} ({})", name, reference.url, reference.kind));
+            }
+        }
+        self.sink.push_page(name, content)
+    }
+
+    fn page_count(&self) -> usize {
+        self.sink.page_count()
+    }
 }
 
-/// Replace with empty string all matches of `regex` in `text`.
-pub fn filter_prefix(text: &str, regex: &Regex) -> String {
-    regex.replace(text, "").to_string()
+/// Print how the stats changed between the two graphs of `--compare`.
+fn print_comparison(opt: &Opt) -> Result<(), anyhow::Error> {
+    let changes = comparison(opt)?;
+    if opt.compare_json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else {
+        let table = comparison_table(&changes, std::io::stdout().is_terminal());
+        print!("{}", table);
+    }
+    Ok(())
 }
 
-/// Remove the trailing slash of `text`, if applicable.
-pub fn remove_trailing_slash(mut text: String) -> String {
-    if text.ends_with('/') {
-        text.pop();
+/// The changes between the stats of the two `--compare` graphs, measured from the same roots
+/// as `--stats-json`.
+fn comparison(opt: &Opt) -> Result<Vec<Change>, anyhow::Error> {
+    if opt.compare.len() != 2 {
+        anyhow::bail!("--compare takes two graphs, got {}", opt.compare.len());
     }
-    text
+    let list = roots_list(opt)?;
+    let mut stats = Vec::new();
+    for path in &opt.compare {
+        let json =
+            fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+        let page_graph = format::json::parse(&json)
+            .context(format!("Could not parse graph in {}", path.display()))?;
+        let graph = page_graph.graph();
+        let root = match (&opt.root, opt.root_auto) {
+            (Some(root), _) => root.as_str(),
+            (None, true) => guess_root(&graph).unwrap_or("index"),
+            (None, false) => "index",
+        };
+        let (roots, _) = find_roots(opt, &graph, root, &list)?;
+        stats.push(GraphStats::new(&graph, &roots));
+    }
+
+    Ok(compare(&stats[0], &stats[1]))
 }
 
-/// Checks if `text` is empty or contains a ':'.
-/// Call this function after filtering out any other http://, mailto:// or text with trailing slashes.
-pub fn is_crawling_leftover(text: &str) -> bool {
-    if text.is_empty() {
-        return false;
+/// `count` and `noun`, with an `s` unless `count` is 1, like `2 pages`.
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
     }
-    if text.contains(':') {
-        return false;
+}
+
+/// The contents of `--roots-from`, empty without it.
+fn roots_list(opt: &Opt) -> Result<String, anyhow::Error> {
+    match &opt.roots_from {
+        Some(path) => {
+            fs::read_to_string(path).context(format!("Could not read {}", path.display()))
+        }
+        None => Ok(String::new()),
     }
-    true
 }
 
-/// Make a vec with the links from the given html.
-pub fn get_urls_from(text: &str) -> Vec<String> {
-    URL.captures_iter(text)
-        .map(|c| c.get(1).unwrap())
-        .map(|m| m.as_str().to_string())
-        .collect()
+/// The pages of `graph` orphans are counted from: `root` first, as stats measure depth from
+/// the first root, then the pages of `list` from `--roots-from` and with
+/// `--auto-section-roots` the section roots, which are also returned on their own.
+fn find_roots<'a>(
+    opt: &Opt,
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    root: &'a str,
+    list: &'a str,
+) -> Result<(Vec<&'a str>, Vec<&'a str>), anyhow::Error> {
+    let (mut roots, mut missing) = split_roots(list, graph);
+    if opt.case_insensitive_lookup {
+        let mut unresolved = Vec::new();
+        for name in missing {
+            match resolve_ignoring_case(graph, name)? {
+                page if graph.contains_node(page) => roots.push(page),
+                name => unresolved.push(name),
+            }
+        }
+        missing = unresolved;
+    }
+    if !missing.is_empty() {
+        eprintln!("warning: roots not in the graph: {}", missing.join(", "));
+    }
+    roots.retain(|&page| page != root);
+    roots.insert(0, root);
+    let sections: Vec<_> = if opt.auto_section_roots {
+        find_section_roots(graph)
+            .into_iter()
+            .filter(|section| !roots.contains(section))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    roots.extend(&sections);
+    Ok((roots, sections))
 }
 
-/// Download the pages at base_url/{urls}.
-pub async fn get_pages(base_url: &str, urls: &[&str]) -> Result<(), anyhow::Error> {
-    for url in urls {
-        let fname = url.to_string();
-        let url = format!("{}{}", base_url, url);
-        let response = reqwest::get(url).await?;
+/// Print a table of every link of `page`, what it normalizes to and whether it is kept.
+fn print_explanation(page: &str, content: &str, config: &Config) {
+    let trace = explain_links(page, content, config);
+    let width = trace
+        .iter()
+        .map(|(url, _)| url.len())
+        .max()
+        .unwrap_or_default();
 
-        let mut dest = { File::create(fname)? };
+    for (url, result) in &trace {
+        match result {
+            Ok(page) => println!("kept     {:width$}  -> {}", url, page, width = width),
+            Err(reason) => println!("dropped  {:width$}  ({})", url, reason, width = width),
+        }
+    }
+    let kept = trace.iter().filter(|(_, result)| result.is_ok()).count();
+    println!("{} links, {} kept", trace.len(), kept);
+}
 
-        let content = response.text().await?;
-        copy(&mut content.as_bytes(), &mut dest)?;
+/// The page of `graph` which `name` names ignoring case, see `find_pages_ignoring_case`, or
+/// `name` itself if it names none.
+fn resolve_ignoring_case<'a>(
+    graph: &GraphMap<&'a str, &'a str, Directed>,
+    name: &'a str,
+) -> Result<&'a str, anyhow::Error> {
+    match find_pages_ignoring_case(graph, name).as_slice() {
+        [] => Ok(name),
+        [page] => Ok(page),
+        pages => anyhow::bail!(
+            "{} is ambiguous, pages differing only in case are {}",
+            name,
+            pages.join(", ")
+        ),
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use page_graph::extract::LinkExtractor;
+    use page_graph::Link;
 
-    #[test]
-    fn regex_matches_url() {
-        let url =
-            r###"<a href='www.traplinked.com'>, some other text, <a  href =   "www.chip.de">"###;
-        assert_eq!(
-            get_urls_from(&url),
-            vec!["www.traplinked.com", "www.chip.de"]
+    /// A directory of `count` pages `0.html`, `1.html`, ... each linking to the next.
+    fn site(count: usize) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..count {
+            let html = format!("<a href=\"/{}.html\">next</a>", i + 1);
+            fs::write(dir.path().join(format!("{}.html", i)), html).unwrap();
+        }
+        dir
+    }
+
+    fn opt(dir: &Path, args: &[&str]) -> Opt {
+        let dir = dir.to_str().unwrap();
+        Opt::from_iter(["page-graph", "-d", dir].iter().chain(args))
+    }
+
+    /// Takes `delay` to extract no links from a page.
+    struct Slow {
+        delay: Duration,
+    }
+
+    impl LinkExtractor for Slow {
+        fn extract<'a>(&self, _content: &'a str, _base: &str) -> Vec<Link<'a>> {
+            std::thread::sleep(self.delay);
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn times_out_with_partial_graph() {
+        let dir = site(5);
+        let expired = opt(dir.path(), &["--timeout", "1ns"]);
+        let (page_graph, crawl_end, _) = crawl(&expired, &Config::default(), &Budget::default())
+            .await
+            .unwrap();
+        assert_eq!(crawl_end, Crawl::TimedOut);
+        assert_eq!(page_graph.graph().node_count(), 0);
+
+        let opt = opt(dir.path(), &["--timeout", "200ms"]);
+        let mut config = Config::default();
+        // Building the first page takes longer than the whole timeout.
+        config.extractors.register(
+            "html",
+            Slow {
+                delay: Duration::from_millis(400),
+            },
         );
+
+        let (page_graph, crawl, _) = crawl(&opt, &config, &Budget::default()).await.unwrap();
+
+        assert_eq!(crawl, Crawl::TimedOut);
+        assert_eq!(page_graph.graph().node_count(), 1);
+        assert_eq!(exit_code(crawl), Some(EXIT_TIMED_OUT));
     }
 
-    #[test]
-    fn malformed_urls() {
-        let url = r###"<a href='www.www.www'> <a>, <a href=www>"###;
-        assert_eq!(get_urls_from(&url), vec!["www.www.www"]);
+    /// Presses Ctrl-C while extracting the links of a page.
+    struct Interrupt {
+        budget: Budget,
     }
 
-    #[test]
-    fn filter_prefixes() {
-        assert_filter("https://www.traplinked.com/hello", "hello");
-        assert_filter("http://www.traplinked.com/thing", "thing");
-        assert_filter("http://www.traplinked.com/tag/this", "tag/this");
-        assert_filter("http://www.traplinked.com/author/who", "author/who");
+    impl LinkExtractor for Interrupt {
+        fn extract<'a>(&self, _content: &'a str, _base: &str) -> Vec<Link<'a>> {
+            self.budget.cancel();
+            Vec::new()
+        }
     }
 
-    fn assert_filter(text: &str, desired: &str) {
-        let actual = filter_prefix(text, &TRAPL_PREFIXES);
-        assert_eq!(desired, actual);
+    #[tokio::test]
+    async fn interrupts_with_partial_graph() {
+        let dir = site(5);
+        let opt = opt(dir.path(), &[]);
+        let budget = Budget::default();
+        let mut config = Config::default();
+        config.extractors.register(
+            "html",
+            Interrupt {
+                budget: budget.clone(),
+            },
+        );
+
+        let (page_graph, crawl, _) = crawl(&opt, &config, &budget).await.unwrap();
+
+        assert_eq!(crawl, Crawl::Interrupted);
+        assert_eq!(page_graph.graph().node_count(), 1);
+        assert_eq!(exit_code(crawl), Some(EXIT_INTERRUPTED));
     }
 
-    #[test]
-    fn filters_regexes() {
-        let items = vec![
-            "hello@".to_string(),
-            "hel!lo".to_string(),
-            "hello".to_string(),
-        ];
-        let results = filter_regex(&items, &Regex::new(r".*@.*").unwrap());
-        assert_eq!(results, vec!["hello@".to_string()]);
+    #[tokio::test]
+    async fn writes_graph_when_interrupted() {
+        let dir = site(5);
+        let out = tempfile::tempdir().unwrap();
+        let output = out.path().join("graph.dot");
+        let opt = opt(dir.path(), &["-o", output.to_str().unwrap()]);
+        let budget = Budget::default();
+        budget.cancel();
+
+        let crawl = run(&opt, &budget).await.unwrap();
+
+        assert_eq!(crawl, Crawl::Interrupted);
+        assert!(fs::read_to_string(&output).unwrap().starts_with("digraph"));
+        assert_eq!(exit_code(Crawl::Complete), None);
     }
 
     #[test]
-    fn removes_trailing_slash() {
-        assert_eq!(remove_trailing_slash("test/".to_string()), "test");
-        assert_eq!(remove_trailing_slash("test".to_string()), "test");
-        assert_eq!(remove_trailing_slash("/".to_string()), "");
-        assert_eq!(remove_trailing_slash("t/e/s/t/".to_string()), "t/e/s/t");
+    fn truncates_reports() {
+        let items = ["a", "b", "c", "d"];
+        let mut out = Vec::new();
+        let reporter = Reporter {
+            limit: Some(2),
+            output: None,
+            dir: None,
+        };
+        reporter
+            .write_list(&mut out, "orphans", "orphans", &items)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "orphans (4):\n  a\n  b\n  ... and 2 more\n"
+        );
+
+        // The full list goes next to the output file, but only if truncated.
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("graph.dot");
+        let full = dir.path().join("graph.orphans.txt");
+        let mut out = Vec::new();
+        let reporter = Reporter {
+            limit: Some(2),
+            output: Some(&output),
+            dir: None,
+        };
+        reporter
+            .write_list(&mut out, "orphans", "orphans", &items)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&full).unwrap(), "a\nb\nc\nd\n");
+        let footer = format!("  ... and 2 more, see {}\n", full.display());
+        assert!(String::from_utf8(out).unwrap().ends_with(&footer));
+
+        fs::remove_file(&full).unwrap();
+        reporter
+            .write_list(&mut Vec::new(), "orphans", "orphans", &items[..2])
+            .unwrap();
+        assert!(!full.exists());
     }
 
     #[test]
-    fn checks_tag() {
-        assert!(!is_crawling_leftover(""));
-        assert!(!is_crawling_leftover("mailto:lchereti"));
-        assert!(is_crawling_leftover("/author/lchereti"));
+    fn limits_reports_on_terminal() {
+        assert_eq!(
+            Reporter::default_limit(None, true),
+            Some(INTERACTIVE_REPORT_LIMIT)
+        );
+        assert_eq!(Reporter::default_limit(None, false), None);
+        assert_eq!(Reporter::default_limit(Some(5), true), Some(5));
+        assert_eq!(Reporter::default_limit(Some(5), false), Some(5));
+    }
+
+    #[tokio::test]
+    async fn writes_reports_to_output_dir() {
+        let dir = site(3);
+        let out = tempfile::tempdir().unwrap();
+        let output_dir = out.path().join("report");
+        let opt = opt(
+            dir.path(),
+            &[
+                "--output-dir",
+                output_dir.to_str().unwrap(),
+                "--root",
+                "1.html",
+                "--find-orphans",
+                "true",
+                "--leaf-report",
+            ],
+        );
+
+        run(&opt, &Budget::default()).await.unwrap();
+
+        let mut files: Vec<_> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort_unstable();
+        assert_eq!(
+            files,
+            vec![
+                "graph.dot",
+                "leaves.txt",
+                "orphans.txt",
+                "self-links.txt",
+                "stats.json"
+            ]
+        );
+        // Written in full even if short, or empty.
+        assert_eq!(
+            fs::read_to_string(output_dir.join("self-links.txt")).unwrap(),
+            ""
+        );
+        assert_eq!(
+            fs::read_to_string(output_dir.join("orphans.txt")).unwrap(),
+            "0.html\n"
+        );
     }
 
     #[test]
-    fn makes_graph_map() {
-        let mut data = HashMap::new();
-        data.insert("a".to_string(), vec!["b".to_string(), "c".to_string()]);
-        data.insert("b".to_string(), vec!["c".to_string()]);
-        data.insert("c".to_string(), vec![]);
+    fn profiles_crawl() {
+        let dir = site(3);
+        let out = tempfile::tempdir().unwrap();
+        let trace = out.path().join("trace.json");
+        let opt = opt(dir.path(), &[]);
+        let (layer, guard) = profile_layer(&trace);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        // On the current thread only, where the subscriber is the default.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            runtime
+                .block_on(crawl(&opt, &Config::default(), &Budget::default()))
+                .unwrap()
+        });
+        drop(guard);
+
+        let trace: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&trace).unwrap()).unwrap();
+        let spans: HashSet<_> = trace
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|event| event["name"].as_str())
+            .collect();
+        for span in ["crawl", "build", "page"] {
+            assert!(spans.contains(span), "no {} span in {:?}", span, spans);
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_orphans_from_all_roots() {
+        let dir = site(3);
+        let out = tempfile::tempdir().unwrap();
+        let roots = out.path().join("roots.txt");
+        fs::write(&roots, "0.html\n").unwrap();
+        let stats = out.path().join("stats.json");
+        let output = out.path().join("graph.csv");
+        let opt = opt(
+            dir.path(),
+            &[
+                "--root",
+                "1.html",
+                "--roots-from",
+                roots.to_str().unwrap(),
+                "--stats-json",
+                stats.to_str().unwrap(),
+                "-f",
+                "nodes-csv",
+                "-o",
+                output.to_str().unwrap(),
+            ],
+        );
+
+        run(&opt, &Budget::default()).await.unwrap();
+
+        let stats: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&stats).unwrap()).unwrap();
+        assert_eq!(stats["orphan_count"], 0);
+        assert_eq!(stats["max_depth"], 2);
+        let csv = fs::read_to_string(&output).unwrap();
+        let is_orphan: Vec<_> = csv
+            .lines()
+            .skip(1)
+            .map(|row| row.split(',').nth(4).unwrap())
+            .collect();
+        assert_eq!(is_orphan, vec!["false"; 4]);
+    }
+
+    #[tokio::test]
+    async fn counts_orphans_from_section_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("blog")).unwrap();
+        fs::write(dir.path().join("index.html"), "<a href=\"/about.html\">").unwrap();
+        fs::write(
+            dir.path().join("blog/index.html"),
+            "<a href=\"/blog/post.html\">",
+        )
+        .unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let stats = out.path().join("stats.json");
+        let output = out.path().join("summary.md");
+        let args = [
+            "--root",
+            "index.html",
+            "--stats-json",
+            stats.to_str().unwrap(),
+            "-f",
+            "summary-md",
+            "-o",
+            output.to_str().unwrap(),
+        ];
 
-        let graph = make_page_graph(&data);
+        for (auto, orphans) in [(false, 2), (true, 0)] {
+            let mut args = args.to_vec();
+            if auto {
+                args.push("--auto-section-roots");
+            }
+            run(&opt(dir.path(), &args), &Budget::default())
+                .await
+                .unwrap();
 
-        println!("{:?}", Dot::with_config(&graph, &[Config::EdgeNoLabel]));
+            let stats: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(&stats).unwrap()).unwrap();
+            assert_eq!(stats["orphan_count"], orphans);
+            let summary = fs::read_to_string(&output).unwrap();
+            assert!(summary.contains(&format!("| Orphans | {} |", orphans)));
+        }
+    }
 
-        assert_eq!(graph.edge_count(), 3);
-        assert_eq!(graph.node_count(), 3);
-        assert!(graph.contains_node("a"));
-        assert!(graph.contains_node("b"));
-        assert!(graph.contains_node("c"));
+    #[tokio::test]
+    async fn allows_orphans_of_hashed_pages() {
+        let dir = site(3);
+        let out = tempfile::tempdir().unwrap();
+        let output = out.path().join("summary.md");
+        let map = out.path().join("map.tsv");
+        let args = [
+            "--root",
+            "1.html",
+            "-f",
+            "summary-md",
+            "-o",
+            output.to_str().unwrap(),
+        ];
 
-        assert!(graph.contains_edge("a", "b"));
-        assert!(graph.contains_edge("a", "c"));
-        assert!(graph.contains_edge("b", "c"));
+        for (allow, hash) in [(false, true), (true, false), (true, true)] {
+            let mut args = args.to_vec();
+            if allow {
+                args.extend(&["--orphan-allow-regex", r"^0\.html$"]);
+            }
+            if hash {
+                args.extend(&["--hash-nodes", map.to_str().unwrap()]);
+            }
+            run(&opt(dir.path(), &args), &Budget::default())
+                .await
+                .unwrap();
+
+            let summary = fs::read_to_string(&output).unwrap();
+            assert_eq!(
+                summary.contains("<summary>Orphans (1)</summary>"),
+                !allow,
+                "{}",
+                summary
+            );
+        }
+    }
+
+    #[test]
+    fn compares_orphans_from_all_roots() {
+        let out = tempfile::tempdir().unwrap();
+        let old = out.path().join("old.json");
+        let new = out.path().join("new.json");
+        let mut links = HashMap::new();
+        links.insert("index".to_string(), vec!["a".to_string()]);
+        links.insert("lost".to_string(), vec!["found".to_string()]);
+        let page_graph = PageGraph::from(links);
+        fs::write(&old, format::json::render(&page_graph).unwrap()).unwrap();
+        fs::write(&new, format::json::render(&page_graph).unwrap()).unwrap();
+        let roots = out.path().join("roots.txt");
+        fs::write(&roots, "lost\n").unwrap();
+
+        let orphans = |args: &[&str]| {
+            let args: Vec<_> = ["page-graph", "--compare", old.to_str().unwrap()]
+                .iter()
+                .chain(&["--compare", new.to_str().unwrap()])
+                .chain(args)
+                .copied()
+                .collect();
+            let changes = comparison(&Opt::from_iter(args)).unwrap();
+            changes
+                .into_iter()
+                .find(|change| change.metric == "orphans")
+                .unwrap()
+                .new
+        };
+        assert_eq!(orphans(&[]), Some(2));
+        assert_eq!(orphans(&["--roots-from", roots.to_str().unwrap()]), Some(0));
+    }
+
+    #[test]
+    fn pluralizes_by_count() {
+        assert_eq!(plural(1, "parent"), "1 parent");
+        assert_eq!(plural(2, "pagination page"), "2 pagination pages");
+        assert_eq!(plural(0, "link"), "0 links");
     }
 }
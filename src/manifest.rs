@@ -0,0 +1,88 @@
+//! Lists of sites processed in one run with `--manifest`.
+
+use anyhow::Context;
+
+use serde::Deserialize;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A JSON manifest, `{"sites": [...]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub sites: Vec<Site>,
+}
+
+/// One site of a manifest. Settings left out are taken from the command line.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Site {
+    /// Directory or archive with the pages, like `--directory`.
+    pub directory: PathBuf,
+    /// Like `--domain`.
+    pub domain: Option<String>,
+    /// Like `--root`.
+    pub root: Option<String>,
+    /// Like `--output`.
+    pub output: Option<PathBuf>,
+}
+
+impl Manifest {
+    /// Read the manifest at `path`. Relative paths in it are relative to the manifest, so it can
+    /// be moved together with the sites.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let json =
+            fs::read_to_string(path).context(format!("Could not read {}", path.display()))?;
+        let mut manifest: Manifest =
+            serde_json::from_str(&json).context(format!("Invalid manifest {}", path.display()))?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for site in &mut manifest.sites {
+            site.directory = base.join(&site.directory);
+            site.output = site.output.as_ref().map(|output| base.join(output));
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loads_sites_relative_to_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sites.json");
+        fs::write(
+            &path,
+            r#"{"sites": [
+                {"directory": "shop", "domain": "shop.example.com", "output": "out/shop.dot"},
+                {"directory": "/srv/blog", "root": "start"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(
+            manifest.sites,
+            vec![
+                Site {
+                    directory: dir.path().join("shop"),
+                    domain: Some("shop.example.com".to_string()),
+                    root: None,
+                    output: Some(dir.path().join("out/shop.dot")),
+                },
+                Site {
+                    directory: PathBuf::from("/srv/blog"),
+                    domain: None,
+                    root: Some("start".to_string()),
+                    output: None,
+                }
+            ]
+        );
+
+        fs::write(&path, r#"{"sites": [{"directory": "a", "dommain": "x"}]}"#).unwrap();
+        assert!(Manifest::load(&path).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use pulldown_cmark::{CowStr, Event, Parser, Tag, TagEnd};
+
+use std::borrow::Cow;
+
+/// Whether the page `name` is a Markdown file, judging by its extension.
+pub fn is_markdown(name: &str) -> bool {
+    name.rsplit('/')
+        .next()
+        .and_then(|file| file.rsplit_once('.'))
+        .is_some_and(|(_, extension)| {
+            extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown")
+        })
+}
+
+/// Make a vec with the link destinations of the given Markdown,
+/// covering inline `[text](url)`, reference-style `[text][id]` and autolinks.
+pub fn get_urls_from(text: &str) -> Vec<String> {
+    urls(text).map(Cow::into_owned).collect()
+}
+
+/// Lazily find the link destinations of the given Markdown, borrowing from it where possible.
+pub fn urls(text: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    Parser::new(text).filter_map(|event| match event {
+        Event::Start(Tag::Link { dest_url, .. }) => Some(match dest_url {
+            CowStr::Borrowed(url) => Cow::Borrowed(url),
+            url => Cow::Owned(url.to_string()),
+        }),
+        _ => None,
+    })
+}
+
+/// Like `urls`, pairing each destination with the text of the link.
+pub fn links(text: &str) -> impl Iterator<Item = (Cow<'_, str>, String)> {
+    let mut parser = Parser::new(text);
+    std::iter::from_fn(move || {
+        let url = parser.by_ref().find_map(|event| match event {
+            Event::Start(Tag::Link { dest_url, .. }) => Some(match dest_url {
+                CowStr::Borrowed(url) => Cow::Borrowed(url),
+                url => Cow::Owned(url.to_string()),
+            }),
+            _ => None,
+        })?;
+        let mut label = String::new();
+        for event in parser.by_ref() {
+            match event {
+                Event::End(TagEnd::Link) => break,
+                Event::Text(text) | Event::Code(text) => label.push_str(&text),
+                Event::SoftBreak | Event::HardBreak => label.push(' '),
+                _ => {}
+            }
+        }
+        Some((url, label))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_markdown() {
+        assert!(is_markdown("guide.md"));
+        assert!(is_markdown("docs/README.MD"));
+        assert!(is_markdown("notes.markdown"));
+        assert!(!is_markdown("index"));
+        assert!(!is_markdown("md/index.html"));
+    }
+
+    #[test]
+    fn gets_inline_and_reference_links() {
+        let text = "See [the guide](guide.md) and [the team][team].\n\n\
+                    Or <https://www.traplinked.com/faq>.\n\n\
+                    [team]: https://www.traplinked.com/team/\n";
+        assert_eq!(
+            get_urls_from(text),
+            vec![
+                "guide.md",
+                "https://www.traplinked.com/team/",
+                "https://www.traplinked.com/faq",
+            ]
+        );
+    }
+
+    #[test]
+    fn gets_link_texts() {
+        let text = "[Back to *top*](#top) and <https://example.com>\n";
+        let links: Vec<_> = links(text).collect();
+        assert_eq!(links[0], (Cow::Borrowed("#top"), "Back to top".to_string()));
+        assert_eq!(links[1].1, "https://example.com");
+    }
+}
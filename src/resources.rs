@@ -0,0 +1,283 @@
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+use std::collections::HashSet;
+use std::fmt;
+
+lazy_static! {
+    static ref TAG: Regex =
+        Regex::new(r###"(?is)<(a|script|img|link|iframe|source|video|audio|embed)\b([^>]*)>"###)
+            .unwrap();
+    static ref SRC: Regex =
+        Regex::new(r###"(?is)(?:^|\s)(?:src|href)\s*=\s*(?:"([^"]*)"|'([^']*)')"###).unwrap();
+    static ref REL: Regex = Regex::new(r###"(?is)(?:^|\s)rel\s*=\s*(?:"([^"]*)"|'([^']*)')"###).unwrap();
+    static ref CANONICAL: Regex =
+        Regex::new(r###"(?is)<link\b[^>]*\brel\s*=\s*["']canonical["'][^>]*>"###).unwrap();
+    static ref META_ROBOTS: Regex =
+        Regex::new(r###"(?is)<meta\b[^>]*\bname\s*=\s*["']robots["'][^>]*>"###).unwrap();
+    static ref CONTENT: Regex =
+        Regex::new(r###"(?is)(?:^|\s)content\s*=\s*(?:"([^"]*)"|'([^']*)')"###).unwrap();
+    static ref ELEMENT: Regex = Regex::new(r###"(?is)<([a-z][a-z0-9-]*)\b([^>]*)>"###).unwrap();
+    static ref ID: Regex = Regex::new(r###"(?is)(?:^|\s)id\s*=\s*(?:"([^"]*)"|'([^']*)')"###).unwrap();
+    static ref NAME: Regex =
+        Regex::new(r###"(?is)(?:^|\s)name\s*=\s*(?:"([^"]*)"|'([^']*)')"###).unwrap();
+    static ref OG_URL: Regex = Regex::new(
+        r###"(?is)<meta\b[^>]*\bproperty\s*=\s*["']og:url["'][^>]*\bcontent\s*=\s*["']([^"']*)["']"###
+    )
+    .unwrap();
+}
+
+/// What a reference in a page is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// A hyperlink, `<a href>`.
+    Anchor,
+    /// `<script src>`.
+    Script,
+    /// `<img src>` or an icon `<link>`.
+    Image,
+    /// A stylesheet `<link>`.
+    Style,
+    /// `<iframe src>` or `<embed src>`.
+    Frame,
+    /// `<video>`, `<audio>` or `<source>` `src`.
+    Media,
+}
+
+impl LinkKind {
+    /// Whether the browser loads the reference along with the page, unlike the page's links.
+    pub fn is_resource(self) -> bool {
+        self != LinkKind::Anchor
+    }
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            LinkKind::Anchor => "anchor",
+            LinkKind::Script => "script",
+            LinkKind::Image => "image",
+            LinkKind::Style => "style",
+            LinkKind::Frame => "frame",
+            LinkKind::Media => "media",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// A URL referenced by a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub kind: LinkKind,
+    pub url: String,
+}
+
+/// Get the links and resource references of the given html in document order.
+/// `<link>` elements other than stylesheets and icons are skipped.
+pub fn get_references(html: &str) -> Vec<Reference> {
+    TAG.captures_iter(html)
+        .filter_map(|tag| {
+            let attributes = tag.get(2).unwrap().as_str();
+            let kind = match tag[1].to_ascii_lowercase().as_str() {
+                "a" => LinkKind::Anchor,
+                "script" => LinkKind::Script,
+                "img" => LinkKind::Image,
+                "iframe" | "embed" => LinkKind::Frame,
+                "video" | "audio" | "source" => LinkKind::Media,
+                _ => {
+                    let rel = attribute(&REL, attributes)?.to_ascii_lowercase();
+                    let mut rel = rel.split_whitespace();
+                    if rel.clone().any(|rel| rel == "stylesheet") {
+                        LinkKind::Style
+                    } else if rel.any(|rel| rel == "icon") {
+                        LinkKind::Image
+                    } else {
+                        return None;
+                    }
+                }
+            };
+            let url = attribute(&SRC, attributes)?.trim().to_string();
+            Some(Reference { kind, url })
+        })
+        .collect()
+}
+
+/// The value of the first attribute matching `regex` with the value in group 1 or 2.
+fn attribute<'a>(regex: &Regex, attributes: &'a str) -> Option<&'a str> {
+    let captures = regex.captures(attributes)?;
+    Some(
+        captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .unwrap()
+            .as_str(),
+    )
+}
+
+/// The `rel` attribute of the given tag.
+pub fn rel(tag: &str) -> Option<&str> {
+    attribute(&REL, tag)
+}
+
+/// The URL the page says it is served from, by its canonical link or else its `og:url`.
+pub fn declared_url(html: &str) -> Option<String> {
+    let canonical = CANONICAL
+        .find(html)
+        .and_then(|link| attribute(&SRC, link.as_str()));
+    let og_url = || OG_URL.captures(html).map(|c| c.get(1).unwrap().as_str());
+    canonical
+        .or_else(og_url)
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// What a page asks search engines to do with it in `<meta name="robots">`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetaRobots {
+    /// Leave the page out of the index, `noindex` or `none`.
+    pub noindex: bool,
+    /// Don't follow the links of the page, `nofollow` or `none`.
+    pub nofollow: bool,
+}
+
+/// Parse the `<meta name="robots">` of the given html, all false if there is none.
+pub fn meta_robots(html: &str) -> MetaRobots {
+    let mut robots = MetaRobots::default();
+    let content = META_ROBOTS
+        .find(html)
+        .and_then(|meta| attribute(&CONTENT, meta.as_str()));
+    for directive in content.unwrap_or("").split(',') {
+        match directive.trim().to_ascii_lowercase().as_str() {
+            "noindex" => robots.noindex = true,
+            "nofollow" => robots.nofollow = true,
+            "none" => {
+                robots.noindex = true;
+                robots.nofollow = true;
+            }
+            _ => {}
+        }
+    }
+    robots
+}
+
+/// The fragments the given html can be scrolled to: the `id` of any element and the `name` of
+/// `<a>` elements.
+pub fn anchor_targets(html: &str) -> HashSet<String> {
+    ELEMENT
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let attributes = captures.get(2).unwrap().as_str();
+            let id = attribute(&ID, attributes);
+            if id.is_none() && captures[1].eq_ignore_ascii_case("a") {
+                attribute(&NAME, attributes)
+            } else {
+                id
+            }
+        })
+        .filter(|target| !target.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Find resources loaded over `http://` by a page declaring an `https://` URL.
+pub fn find_mixed_content(html: &str) -> Vec<Reference> {
+    let secure =
+        declared_url(html).is_some_and(|url| url.to_ascii_lowercase().starts_with("https://"));
+    if !secure {
+        return Vec::new();
+    }
+    get_references(html)
+        .into_iter()
+        .filter(|reference| reference.kind.is_resource())
+        .filter(|reference| reference.url.to_ascii_lowercase().starts_with("http://"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head>
+        <link rel="canonical" href="https://www.traplinked.com/team" />
+        <link rel='stylesheet' href='http://cdn.example.com/site.css'>
+        <link rel="alternate" href="http://www.traplinked.com/en/team">
+        <script src="https://cdn.example.com/app.js"></script>
+        </head><body>
+        <a href="http://www.traplinked.com/shop">shop</a>
+        <IMG SRC="http://www.traplinked.com/logo.png" alt="">
+        <iframe src="http://maps.example.com/embed"></iframe>
+        </body></html>"#;
+
+    #[test]
+    fn classifies_references() {
+        let kinds: Vec<_> = get_references(PAGE).iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LinkKind::Style,
+                LinkKind::Script,
+                LinkKind::Anchor,
+                LinkKind::Image,
+                LinkKind::Frame
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_declared_url() {
+        assert_eq!(
+            declared_url(PAGE).as_deref(),
+            Some("https://www.traplinked.com/team")
+        );
+        let og = r#"<meta property="og:url" content="http://example.com/" />"#;
+        assert_eq!(declared_url(og).as_deref(), Some("http://example.com/"));
+        assert_eq!(declared_url("<title>none</title>"), None);
+    }
+
+    #[test]
+    fn parses_meta_robots() {
+        let robots = meta_robots(r#"<meta content="NOINDEX, follow" name="robots">"#);
+        assert_eq!(
+            robots,
+            MetaRobots {
+                noindex: true,
+                nofollow: false
+            }
+        );
+        let robots = meta_robots(r#"<meta name='robots' content='none'>"#);
+        assert!(robots.noindex && robots.nofollow);
+        assert_eq!(meta_robots(PAGE), MetaRobots::default());
+    }
+
+    #[test]
+    fn finds_anchor_targets() {
+        let html = r#"<h2 id="intro">Intro</h2><a name='legacy'></a>
+            <input name="query"><section class="x" ID="Faq"><p data-id="no"></p>"#;
+        let mut targets: Vec<_> = anchor_targets(html).into_iter().collect();
+        targets.sort_unstable();
+        assert_eq!(targets, vec!["Faq", "intro", "legacy"]);
+    }
+
+    #[test]
+    fn finds_mixed_content() {
+        let urls: Vec<_> = find_mixed_content(PAGE)
+            .into_iter()
+            .map(|r| r.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://cdn.example.com/site.css",
+                "http://www.traplinked.com/logo.png",
+                "http://maps.example.com/embed"
+            ]
+        );
+
+        let insecure = PAGE.replace(
+            "https://www.traplinked.com/team",
+            "http://www.traplinked.com/team",
+        );
+        assert!(find_mixed_content(&insecure).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+use std::collections::HashMap;
+
+use crate::{page_name, Config};
+
+lazy_static! {
+    static ref ENTRY: Regex = Regex::new(r###"(?is)<url>(.*?)</url>"###).unwrap();
+    static ref LOC: Regex = Regex::new(r###"(?is)<loc>\s*(.*?)\s*</loc>"###).unwrap();
+    static ref PRIORITY: Regex =
+        Regex::new(r###"(?is)<priority>\s*(.*?)\s*</priority>"###).unwrap();
+}
+
+/// Priority of sitemap entries without a `<priority>`, as defined by sitemaps.org.
+pub const DEFAULT_PRIORITY: f64 = 0.5;
+
+/// One `<url>` of a sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    /// The absolute URL in `<loc>`.
+    pub loc: String,
+    /// The `<priority>`, or `DEFAULT_PRIORITY`.
+    pub priority: f64,
+}
+
+/// Parse the `<url>` entries of a sitemap in document order.
+/// Entries without a `<loc>` are skipped, unparseable priorities count as the default.
+pub fn parse(xml: &str) -> Vec<SitemapEntry> {
+    ENTRY
+        .captures_iter(xml)
+        .filter_map(|entry| {
+            let entry = entry.get(1).unwrap().as_str();
+            let loc = LOC.captures(entry)?.get(1).unwrap().as_str();
+            let priority = PRIORITY
+                .captures(entry)
+                .and_then(|c| c.get(1).unwrap().as_str().parse().ok())
+                .unwrap_or(DEFAULT_PRIORITY);
+            Some(SitemapEntry {
+                loc: loc.replace("&amp;", "&"),
+                priority,
+            })
+        })
+        .collect()
+}
+
+/// A sitemap page which is harder to reach than its sitemap rank suggests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuriedPage {
+    /// Name of the page in the graph.
+    pub page: String,
+    /// Its sitemap priority.
+    pub priority: f64,
+    /// Its distance from the root, `None` if unreachable.
+    pub depth: Option<usize>,
+    /// How many places lower it ranks by depth than by sitemap order.
+    pub displacement: usize,
+}
+
+/// Compare the sitemap order of pages with their depth from `root`, worst mismatches first.
+///
+/// The sitemap ranks pages by priority, ties broken by position in the sitemap. By depth, a page
+/// ranks behind all pages which are strictly shallower, unreachable pages rank last.
+/// Pages ranking lower by depth are reported, entries not naming a crawled or linked page
+/// (`pages`) are ignored.
+pub fn find_buried_pages(
+    entries: &[SitemapEntry],
+    pages: &[&str],
+    depths: &HashMap<&str, usize>,
+    root: &str,
+    config: &Config,
+) -> Vec<BuriedPage> {
+    let mut ranked: Vec<(String, f64)> = Vec::new();
+    for entry in entries {
+        let page = match page_name(&entry.loc, root, config) {
+            Some(page) => page,
+            None => continue,
+        };
+        if pages.contains(&page.as_str()) && ranked.iter().all(|(p, _)| *p != page) {
+            ranked.push((page, entry.priority));
+        }
+    }
+    // Stable, so equal priorities keep their sitemap order.
+    ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let depth_of = |page: &str| depths.get(page).copied();
+    let mut buried: Vec<_> = ranked
+        .iter()
+        .enumerate()
+        .filter_map(|(rank, (page, priority))| {
+            let depth = depth_of(page);
+            let depth_rank = ranked
+                .iter()
+                .filter(|(other, _)| match (depth_of(other), depth) {
+                    (Some(other), Some(depth)) => other < depth,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                })
+                .count();
+            Some(BuriedPage {
+                page: page.clone(),
+                priority: *priority,
+                depth,
+                displacement: depth_rank.checked_sub(rank).filter(|&d| d > 0)?,
+            })
+        })
+        .collect();
+    buried.sort_by(|a, b| {
+        b.displacement
+            .cmp(&a.displacement)
+            .then_with(|| a.page.cmp(&b.page))
+    });
+    buried
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{depths, make_page_graph};
+
+    #[test]
+    fn parses_entries() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://www.traplinked.com/</loc><priority>1.0</priority></url>
+              <url>
+                <loc> https://www.traplinked.com/shop?a=1&amp;b=2 </loc>
+              </url>
+              <url><priority>0.3</priority></url>
+            </urlset>"#;
+
+        assert_eq!(
+            parse(xml),
+            vec![
+                SitemapEntry {
+                    loc: "https://www.traplinked.com/".to_string(),
+                    priority: 1.0
+                },
+                SitemapEntry {
+                    loc: "https://www.traplinked.com/shop?a=1&b=2".to_string(),
+                    priority: DEFAULT_PRIORITY
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_buried_pages() {
+        let mut data = HashMap::new();
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        data.insert("index".to_string(), page(&["blog"]));
+        data.insert("blog".to_string(), page(&["blog/2019"]));
+        data.insert("blog/2019".to_string(), page(&["pricing"]));
+        data.insert("contact".to_string(), page(&[]));
+        let graph = make_page_graph(&data);
+        let pages: Vec<_> = graph.nodes().collect();
+        let depths = depths(&graph, "index");
+
+        let entry = |loc: &str, priority| SitemapEntry {
+            loc: format!("https://www.traplinked.com/{}", loc),
+            priority,
+        };
+        let entries = vec![
+            entry("", 1.0),
+            entry("pricing", 0.9),
+            entry("contact", 0.8),
+            entry("blog", 0.5),
+            entry("missing", 0.9),
+        ];
+        let buried = find_buried_pages(&entries, &pages, &depths, "index", &Config::default());
+
+        let summary: Vec<_> = buried
+            .iter()
+            .map(|b| (b.page.as_str(), b.depth, b.displacement))
+            .collect();
+        assert_eq!(summary, vec![("contact", None, 1), ("pricing", Some(3), 1)]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Keeping crawled pages on disk instead of in memory, for `--spill`.
+//!
+//! Pages go into a temporary sled database as they are read and are streamed from it when the
+//! graph is built, so only their links stay in memory, not their html. Every page is written to
+//! and read back from disk once, which makes crawls noticeably slower and needs about as much
+//! free disk space as the pages take. Only worth it for sites which don't fit in memory.
+
+use anyhow::Context;
+
+use tempfile::TempDir;
+
+use crate::PageSink;
+
+/// Memory sled may use for caching, in bytes.
+const CACHE_CAPACITY: u64 = 64 * 1024 * 1024;
+
+/// Crawled pages in a sled database in a temporary directory, removed when this is dropped.
+pub struct SpilledPages {
+    db: sled::Db,
+    count: usize,
+    _dir: TempDir,
+}
+
+impl SpilledPages {
+    /// Open an empty store.
+    pub fn new() -> Result<Self, anyhow::Error> {
+        let dir = tempfile::tempdir().context("Could not create a directory for spilled pages")?;
+        let db = sled::Config::new()
+            .path(dir.path().join("pages"))
+            .cache_capacity(CACHE_CAPACITY)
+            .open()
+            .context(format!("Could not open {}", dir.path().display()))?;
+        Ok(Self {
+            db,
+            count: 0,
+            _dir: dir,
+        })
+    }
+
+    /// The (page name, html) pairs, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = Result<(String, String), anyhow::Error>> + '_ {
+        self.db.iter().map(|entry| {
+            let (name, content) = entry?;
+            Ok((
+                String::from_utf8(name.to_vec())?,
+                String::from_utf8(content.to_vec())?,
+            ))
+        })
+    }
+}
+
+impl PageSink for SpilledPages {
+    /// A page pushed twice replaces its earlier content, as `build_graph_from_pages` would.
+    fn push_page(&mut self, name: String, content: String) -> Result<(), anyhow::Error> {
+        if self.db.insert(name, content.as_bytes())?.is_none() {
+            self.count += 1;
+        }
+        Ok(())
+    }
+
+    fn page_count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{build_graph_from_pages, Config};
+
+    #[test]
+    fn streams_pages_from_disk() {
+        let pages = vec![
+            (
+                "index".to_string(),
+                r#"<a href="/b"> <a href="/a">"#.to_string(),
+            ),
+            ("b".to_string(), r#"<a href="/index">"#.to_string()),
+            ("a".to_string(), String::new()),
+            ("b".to_string(), r#"<a href="/a">"#.to_string()),
+        ];
+        let mut spilled = SpilledPages::new().unwrap();
+        for (name, content) in pages.clone() {
+            spilled.push_page(name, content).unwrap();
+        }
+        assert_eq!(spilled.page_count(), 3);
+
+        let names: Vec<_> = spilled.iter().map(|page| page.unwrap().0).collect();
+        assert_eq!(names, vec!["a", "b", "index"]);
+
+        let streamed = spilled.iter().map(Result::unwrap);
+        assert_eq!(
+            build_graph_from_pages(streamed, &Config::default()),
+            build_graph_from_pages(pages, &Config::default())
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use petgraph::algo::connected_components;
+use petgraph::graphmap::GraphMap;
+use petgraph::{Directed, Direction};
+
+use serde::Serialize;
+
+use std::fmt::Write;
+
+use crate::{deepest_page, find_unreachable, Reachability};
+
+/// Summary numbers about a page graph, written by `--stats-json`.
+///
+/// The serialized field names are part of the output format: dashboards parse them,
+/// so add new fields rather than renaming existing ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphStats {
+    /// Number of pages, crawled or only linked to.
+    pub node_count: usize,
+    /// Number of distinct page -> page links.
+    pub edge_count: usize,
+    /// Number of pages not reachable from any root.
+    pub orphan_count: usize,
+    /// Number of pages without outgoing links.
+    pub dead_end_count: usize,
+    /// Number of weakly connected components.
+    pub component_count: usize,
+    /// Largest shortest-path distance from the root, `null` if the root is missing.
+    pub max_depth: Option<usize>,
+}
+
+impl GraphStats {
+    /// Compute the stats of `graph`, measuring reachability from `roots` and depth from the
+    /// first of them.
+    pub fn new(graph: &GraphMap<&str, &str, Directed>, roots: &[&str]) -> Self {
+        let dead_end_count = graph
+            .nodes()
+            .filter(|n| {
+                graph
+                    .neighbors_directed(n, Direction::Outgoing)
+                    .next()
+                    .is_none()
+            })
+            .count();
+
+        Self {
+            node_count: graph.node_count(),
+            edge_count: graph.edge_count(),
+            orphan_count: find_unreachable(graph, roots, Reachability::Forward).len(),
+            dead_end_count,
+            component_count: connected_components(graph),
+            max_depth: roots
+                .first()
+                .and_then(|root| deepest_page(graph, root))
+                .map(|deepest| deepest.depth),
+        }
+    }
+}
+
+/// One stat of two graphs side by side, written by `--compare`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub metric: &'static str,
+    pub old: Option<usize>,
+    pub new: Option<usize>,
+    /// `new - old`, `null` if either is missing.
+    pub delta: Option<i64>,
+    /// Whether the new graph is worse at this, like having more orphans.
+    pub regression: bool,
+    /// Whether the new graph is better at this, like having fewer orphans.
+    pub improvement: bool,
+}
+
+/// Compare the stats of two graphs, in the order of the `GraphStats` fields.
+pub fn compare(old: &GraphStats, new: &GraphStats) -> Vec<Change> {
+    // More pages and links are neither good nor bad, more of the rest is.
+    let metrics = [
+        ("nodes", old.node_count, new.node_count, false),
+        ("edges", old.edge_count, new.edge_count, false),
+        ("orphans", old.orphan_count, new.orphan_count, true),
+        ("dead ends", old.dead_end_count, new.dead_end_count, true),
+        ("components", old.component_count, new.component_count, true),
+    ];
+    let mut changes: Vec<_> = metrics
+        .iter()
+        .map(|&(metric, old, new, lower_is_better)| {
+            let delta = new as i64 - old as i64;
+            Change {
+                metric,
+                old: Some(old),
+                new: Some(new),
+                delta: Some(delta),
+                regression: lower_is_better && delta > 0,
+                improvement: lower_is_better && delta < 0,
+            }
+        })
+        .collect();
+    let delta = match (old.max_depth, new.max_depth) {
+        (Some(old), Some(new)) => Some(new as i64 - old as i64),
+        _ => None,
+    };
+    changes.push(Change {
+        metric: "max depth",
+        old: old.max_depth,
+        new: new.max_depth,
+        delta,
+        // Losing the root is worse than any depth.
+        regression: delta.map_or(old.max_depth.is_some() && new.max_depth.is_none(), |d| {
+            d > 0
+        }),
+        improvement: delta.map_or(old.max_depth.is_none() && new.max_depth.is_some(), |d| {
+            d < 0
+        }),
+    });
+    changes
+}
+
+/// Format `changes` as a table with signed deltas, regressions in red and improvements in green
+/// if `color` is set. Missing values are `-`.
+pub fn comparison_table(changes: &[Change], color: bool) -> String {
+    let show = |value: Option<usize>| value.map_or("-".to_string(), |v| v.to_string());
+    let width = changes
+        .iter()
+        .map(|change| change.metric.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut table = String::new();
+    writeln!(
+        table,
+        "{:width$}  {:>8}  {:>8}  {:>8}",
+        "metric",
+        "old",
+        "new",
+        "delta",
+        width = width
+    )
+    .unwrap();
+    for change in changes {
+        let delta = change
+            .delta
+            .map_or("-".to_string(), |delta| format!("{:+}", delta));
+        let delta = format!("{:>8}", delta);
+        let delta = match (color, change.regression, change.improvement) {
+            (true, true, _) => format!("\x1b[31m{}\x1b[0m", delta),
+            (true, _, true) => format!("\x1b[32m{}\x1b[0m", delta),
+            _ => delta,
+        };
+        writeln!(
+            table,
+            "{:width$}  {:>8}  {:>8}  {}",
+            change.metric,
+            show(change.old),
+            show(change.new),
+            delta,
+            width = width
+        )
+        .unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+    use std::collections::HashMap;
+
+    #[test]
+    fn computes_stats() {
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["a".to_string(), "b".to_string()]);
+        data.insert("a".to_string(), vec!["b".to_string()]);
+        data.insert("lost".to_string(), vec!["found".to_string()]);
+
+        let graph = make_page_graph(&data);
+        let stats = GraphStats::new(&graph, &["index"]);
+
+        assert_eq!(
+            stats,
+            GraphStats {
+                node_count: 5,
+                edge_count: 4,
+                orphan_count: 2,
+                dead_end_count: 2,
+                component_count: 2,
+                max_depth: Some(1),
+            }
+        );
+
+        // Pages reachable from any root aren't orphans, depth is from the first.
+        let stats = GraphStats::new(&graph, &["index", "lost"]);
+        assert_eq!(stats.orphan_count, 0);
+        assert_eq!(stats.max_depth, Some(1));
+    }
+
+    #[test]
+    fn serializes_stable_keys() {
+        let data = HashMap::new();
+        let graph = make_page_graph(&data);
+        let json = serde_json::to_value(GraphStats::new(&graph, &["index"])).unwrap();
+
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "component_count",
+                "dead_end_count",
+                "edge_count",
+                "max_depth",
+                "node_count",
+                "orphan_count",
+            ]
+        );
+        assert!(json["max_depth"].is_null());
+    }
+
+    #[test]
+    fn compares_stats() {
+        let page = |links: &[&str]| links.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let mut old = HashMap::new();
+        old.insert("index".to_string(), page(&["a", "b"]));
+        old.insert("lost".to_string(), page(&["found"]));
+        let mut new = HashMap::new();
+        new.insert("index".to_string(), page(&["a"]));
+        new.insert("a".to_string(), page(&["b", "lost"]));
+        new.insert("b".to_string(), page(&["c"]));
+        let (old, new) = (make_page_graph(&old), make_page_graph(&new));
+
+        let changes = compare(
+            &GraphStats::new(&old, &["index"]),
+            &GraphStats::new(&new, &["index"]),
+        );
+
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.metric, c.delta, c.regression, c.improvement))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("nodes", Some(0), false, false),
+                ("edges", Some(1), false, false),
+                ("orphans", Some(-2), false, true),
+                ("dead ends", Some(-1), false, true),
+                ("components", Some(-1), false, true),
+                ("max depth", Some(2), true, false),
+            ]
+        );
+        assert_eq!(
+            comparison_table(&changes[..3], false),
+            "metric        old       new     delta\n\
+             nodes           5         5        +0\n\
+             edges           3         4        +1\n\
+             orphans         2         0        -2\n"
+        );
+        assert!(comparison_table(&changes, true).contains("\x1b[31m      +2\x1b[0m"));
+    }
+}
@@ -0,0 +1,99 @@
+//! Navigation counts from web server access logs.
+//!
+//! Logs must be in the combined log format of Apache and nginx, e.g.
+//!
+//! ```text
+//! 203.0.113.9 - - [10/Oct/2023:13:55:36 +0200] "GET /team HTTP/1.1" 200 2326 "https://www.traplinked.com/" "Mozilla/5.0"
+//! ```
+//!
+//! Each request with a referer on the site counts as one navigation from the referring page to
+//! the requested one. Lines in other formats, failed requests (status 400 and above) and
+//! requests referred from other sites are skipped.
+
+use lazy_static::lazy_static;
+
+use petgraph::graphmap::GraphMap;
+use petgraph::Directed;
+
+use regex::Regex;
+
+use std::collections::HashMap;
+
+use crate::{page_name, Config};
+
+lazy_static! {
+    static ref COMBINED: Regex =
+        Regex::new(r###"^\S+ \S+ \S+ \[[^\]]*\] "[A-Z]+ (\S+)[^"]*" (\d{3}) \S+ "([^"]*)""###)
+            .unwrap();
+}
+
+/// Maps (referring page, requested page) to how often that navigation happened.
+pub type Traffic = HashMap<(String, String), usize>;
+
+/// Count the navigations between pages of the site in a combined format access `log`.
+/// The site's homepage is named `root`.
+pub fn parse_access_log(log: &str, root: &str, config: &Config) -> Traffic {
+    let mut traffic = Traffic::new();
+    for line in log.lines() {
+        let captures = match COMBINED.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        if captures[2].starts_with(['4', '5']) {
+            continue;
+        }
+        let (request, referer) = (&captures[1], &captures[3]);
+        // Referers are absolute, requests relative to the site.
+        if !config.filter.is_match(referer) {
+            continue;
+        }
+        let from = page_name(referer, root, config);
+        let to = page_name(request, root, config);
+        if let (Some(from), Some(to)) = (from, to) {
+            *traffic.entry((from, to)).or_default() += 1;
+        }
+    }
+    traffic
+}
+
+/// Navigations in `traffic` without a matching link in `graph`, most frequent first.
+pub fn find_unexpected<'a>(
+    traffic: &'a Traffic,
+    graph: &GraphMap<&str, &str, Directed>,
+) -> Vec<(&'a str, &'a str, usize)> {
+    let mut unexpected: Vec<_> = traffic
+        .iter()
+        .filter(|((from, to), _)| from != to && !graph.contains_edge(from, to))
+        .map(|((from, to), &count)| (from.as_str(), to.as_str(), count))
+        .collect();
+    unexpected.sort_unstable_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+    unexpected
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::make_page_graph;
+
+    #[test]
+    fn counts_navigations() {
+        let log = r#"203.0.113.9 - - [10/Oct/2023:13:55:36 +0200] "GET /team HTTP/1.1" 200 2326 "https://www.traplinked.com/" "Mozilla/5.0"
+203.0.113.9 - - [10/Oct/2023:13:55:40 +0200] "GET /team HTTP/1.1" 304 0 "https://www.traplinked.com/" "Mozilla/5.0"
+203.0.113.9 - - [10/Oct/2023:13:56:01 +0200] "GET /shop HTTP/1.1" 200 512 "https://www.traplinked.com/team" "Mozilla/5.0"
+203.0.113.9 - - [10/Oct/2023:13:56:07 +0200] "GET /gone HTTP/1.1" 404 0 "https://www.traplinked.com/team" "Mozilla/5.0"
+198.51.100.2 - - [10/Oct/2023:13:57:00 +0200] "GET /shop HTTP/1.1" 200 512 "https://www.google.com/" "Mozilla/5.0"
+198.51.100.2 - - [10/Oct/2023:13:57:02 +0200] "GET /team HTTP/1.1" 200 2326 "-" "Mozilla/5.0"
+not a log line"#;
+
+        let traffic = parse_access_log(log, "index", &Config::default());
+
+        assert_eq!(traffic.len(), 2);
+        assert_eq!(traffic[&("index".to_string(), "team".to_string())], 2);
+        assert_eq!(traffic[&("team".to_string(), "shop".to_string())], 1);
+
+        let mut data = HashMap::new();
+        data.insert("index".to_string(), vec!["team".to_string()]);
+        let graph = make_page_graph(&data);
+        assert_eq!(find_unexpected(&traffic, &graph), vec![("team", "shop", 1)]);
+    }
+}